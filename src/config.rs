@@ -1,15 +1,305 @@
+use crate::domain::{models::{Capabilities, Platform}, templates::Templates};
 use ::serde::Deserialize;
-use ::std::fs;
+use ::std::{fs, path::Path};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     pub required_approvals: usize,
     pub command_prefix: ::std::string::String,
     pub expiry_seconds: u64,
+    #[serde(default = "default_bulk_delay_ms")]
+    pub bulk_delay_ms: u64,
+    pub raid: ::std::option::Option<RaidConfig>,
+    #[serde(default)]
+    pub lockdown: LockdownConfig,
+    #[serde(default)]
+    pub review: ReviewRouting,
+    #[serde(default)]
+    pub discussion: DiscussionConfig,
+    #[serde(default)]
+    pub notice: NoticeConfig,
+    #[serde(default)]
+    pub locale: LocaleConfig,
+    #[serde(default)]
+    pub reactions: ReactionConfig,
+    #[serde(default)]
+    pub cancel: CancelConfig,
+    #[serde(default)]
+    pub permissions: PermissionConfig,
+    #[serde(default)]
+    pub tenants: ::std::vec::Vec<TenantConfig>,
+    #[serde(skip)]
+    pub templates: Templates,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct StaffRoles {
+    pub staff: ::std::string::String,
+    #[serde(default)]
+    pub emergency: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub senior: ::std::option::Option<::std::string::String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenantConfig {
+    pub name: ::std::string::String,
+    #[serde(default)]
+    pub namespace: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub required_approvals: ::std::option::Option<usize>,
+    #[serde(default)]
+    pub cancel: ::std::option::Option<CancelConfig>,
+    #[serde(default)]
+    pub discord: ::std::option::Option<SpaceConfig>,
+    #[serde(default)]
+    pub stoat: ::std::option::Option<SpaceConfig>,
+    #[serde(default)]
+    pub bridges: ::std::vec::Vec<Bridge>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Bridge {
+    pub discord: ::std::string::String,
+    pub stoat: ::std::string::String,
+}
+
+impl TenantConfig {
+    fn from_env(lockdown: &LockdownConfig, review: &ReviewRouting) -> ::anyhow::Result<Self> {
+        let var = |k: &str| ::std::env::var(k).map_err(|_| ::anyhow::anyhow!("{} must be set when no [[tenants]] are configured", k));
+        let roles = |prefix: &str| -> ::anyhow::Result<StaffRoles> {
+            Ok(StaffRoles { staff: var(&format!("{}_STAFF_ROLE_ID", prefix))?, emergency: var(&format!("{}_EMERGENCY_ROLE_ID", prefix)).ok(), senior: var(&format!("{}_SENIOR_ROLE_ID", prefix)).ok() })
+        };
+        Ok(Self {
+            name: "default".into(),
+            namespace: Some(::std::string::String::new()),
+            required_approvals: None,
+            cancel: None,
+            discord: Some(SpaceConfig { id: var("DISCORD_GUILD_ID")?, log: var("DISCORD_LOG_CHANNEL_ID")?, roles: roles("DISCORD")?, lockdown: lockdown.discord.iter().map(u64::to_string).collect(), review: review.discord.clone(), locale: None }),
+            stoat: Some(SpaceConfig { id: ::std::string::String::new(), log: var("STOAT_LOG_CHANNEL_ID")?, roles: roles("STOAT")?, lockdown: lockdown.stoat.clone(), review: review.stoat.clone(), locale: None }),
+            bridges: ::std::vec::Vec::new(),
+        })
+    }
+
+    pub fn namespace(&self) -> ::std::string::String {
+        self.namespace.clone().unwrap_or_else(|| self.name.clone())
+    }
+
+    pub fn space(&self, platform: &Platform) -> ::std::option::Option<&SpaceConfig> {
+        match platform { Platform::Discord => self.discord.as_ref(), Platform::Stoat => self.stoat.as_ref() }
+    }
+
+    pub fn route(&self, platform: &Platform, origin: &Platform, channel: &str) -> ::std::option::Option<::std::string::String> {
+        let space = self.space(platform)?;
+        if platform == origin { return Some(channel.into()); }
+        let bridged = self.bridges.iter().find_map(|b| match platform {
+            Platform::Discord => (b.stoat == channel).then_some(&b.discord),
+            Platform::Stoat => (b.discord == channel).then_some(&b.stoat),
+        });
+        Some(bridged.unwrap_or(&space.log).clone())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpaceConfig {
+    #[serde(default)]
+    pub id: ::std::string::String,
+    pub log: ::std::string::String,
+    #[serde(flatten)]
+    pub roles: StaffRoles,
+    #[serde(default)]
+    pub lockdown: ::std::vec::Vec<::std::string::String>,
+    #[serde(default)]
+    pub review: ReviewConfig,
+    #[serde(default)]
+    pub locale: ::std::option::Option<::std::string::String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReviewConfig {
+    #[serde(default)]
+    pub channel: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub emergency: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub actions: ::std::collections::HashMap<::std::string::String, ::std::string::String>,
+    #[serde(default)]
+    pub thread: bool,
+}
+
+impl ReviewConfig {
+    pub fn channel(&self, action: &str, emergency: bool) -> ::std::option::Option<&::std::string::String> {
+        self.actions.get(action).or(self.emergency.as_ref().filter(|_| emergency)).or(self.channel.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiscussionConfig {
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReviewRouting {
+    #[serde(default)]
+    pub discord: ReviewConfig,
+    #[serde(default)]
+    pub stoat: ReviewConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PermissionConfig {
+    #[serde(default)]
+    pub discord: ::std::collections::HashMap<::std::string::String, ::std::vec::Vec<::std::string::String>>,
+    #[serde(default)]
+    pub stoat: ::std::collections::HashMap<::std::string::String, ::std::vec::Vec<::std::string::String>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CancelPolicy { #[default] Anyone, Author, Senior, Quorum }
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CancelConfig {
+    #[serde(default)]
+    pub default: CancelPolicy,
+    #[serde(default)]
+    pub actions: ::std::collections::HashMap<::std::string::String, CancelPolicy>,
+}
+
+impl CancelConfig {
+    pub fn policy(&self, action: &str) -> CancelPolicy {
+        self.actions.get(action).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReactionConfig {
+    #[serde(default = "default_approve")]
+    pub approve: ::std::string::String,
+    #[serde(default = "default_reject")]
+    pub reject: ::std::string::String,
+    #[serde(default = "default_abstain")]
+    pub abstain: ::std::string::String,
+    #[serde(default = "default_cancel")]
+    pub cancel: ::std::string::String,
+}
+
+impl Default for ReactionConfig {
+    fn default() -> Self { Self { approve: default_approve(), reject: default_reject(), abstain: default_abstain(), cancel: default_cancel() } }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocaleConfig {
+    #[serde(default = "default_locale")]
+    pub discord: ::std::string::String,
+    #[serde(default = "default_locale")]
+    pub stoat: ::std::string::String,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> Self { Self { discord: default_locale(), stoat: default_locale() } }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NoticeConfig {
+    #[serde(default)]
+    pub ban: bool,
+    #[serde(default)]
+    pub kick: bool,
+}
+
+impl NoticeConfig {
+    pub fn enabled(&self, action: &str) -> bool {
+        match action { "ban" => self.ban, "kick" => self.kick, _ => false }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LockdownConfig {
+    #[serde(default)]
+    pub discord: ::std::vec::Vec<u64>,
+    #[serde(default)]
+    pub stoat: ::std::vec::Vec<::std::string::String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RaidConfig {
+    pub join_threshold: usize,
+    pub join_window_seconds: u64,
+    pub flood_threshold: usize,
+    pub flood_window_seconds: u64,
+}
+
+fn default_bulk_delay_ms() -> u64 { 1000 }
+fn default_locale() -> ::std::string::String { "en".into() }
+fn default_approve() -> ::std::string::String { "✅".into() }
+fn default_reject() -> ::std::string::String { "👎".into() }
+fn default_abstain() -> ::std::string::String { "🤷".into() }
+fn default_cancel() -> ::std::string::String { "❌".into() }
+
 impl AppConfig {
     pub fn load(path: &str) -> ::anyhow::Result<Self> {
-        Ok(::toml::from_str(&fs::read_to_string(path)?)?)
+        let mut cfg: Self = ::toml::from_str(&fs::read_to_string(path)?)?;
+        cfg.templates = Templates::load(&Path::new(path).with_file_name("locales"))?;
+        if cfg.tenants.is_empty() { cfg.tenants.push(TenantConfig::from_env(&cfg.lockdown, &cfg.review)?); }
+        for (i, t) in cfg.tenants.iter().enumerate() {
+            if cfg.tenants[..i].iter().any(|o| o.name == t.name) { ::anyhow::bail!("Duplicate tenant name {}", t.name); }
+        }
+        Ok(cfg)
+    }
+
+    pub fn text(&self, tenant: &str, platform: &Platform, key: &str, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.templates.render(self.lang(tenant, platform), key, vars)
+    }
+
+    pub fn tenant(&self, name: &str) -> ::std::option::Option<&TenantConfig> {
+        self.tenants.iter().find(|t| t.name == name)
+    }
+
+    pub fn space(&self, tenant: &str, platform: &Platform) -> ::std::option::Option<&SpaceConfig> {
+        self.tenant(tenant)?.space(platform)
+    }
+
+    pub fn tenant_of(&self, platform: &Platform, id: &str) -> ::std::option::Option<(&TenantConfig, &SpaceConfig)> {
+        self.tenants.iter().find_map(|t| t.space(platform).filter(|s| s.id == id).map(|s| (t, s)))
+    }
+
+    pub fn appeal_tenant(&self, platform: &Platform, name: ::std::option::Option<&str>) -> ::std::option::Option<&TenantConfig> {
+        let mut candidates = self.tenants.iter().filter(|t| t.space(platform).is_some());
+        match name {
+            Some(n) => candidates.find(|t| t.name == n),
+            None => candidates.next().filter(|_| candidates.next().is_none()),
+        }
+    }
+
+    pub fn quorum(&self, tenant: &str) -> usize {
+        self.tenant(tenant).and_then(|t| t.required_approvals).unwrap_or(self.required_approvals)
+    }
+
+    pub fn cancel_policy(&self, tenant: &str, action: &str) -> CancelPolicy {
+        self.tenant(tenant).and_then(|t| t.cancel.as_ref()).unwrap_or(&self.cancel).policy(action)
+    }
+
+    pub fn capabilities(&self, platform: &Platform, roles: &[::std::string::String], fallback: &StaffRoles) -> Capabilities {
+        let matrix = match platform { Platform::Discord => &self.permissions.discord, Platform::Stoat => &self.permissions.stoat };
+        let mut caps: ::std::vec::Vec<::std::string::String> = if !roles.iter().any(|r| matrix.contains_key(r)) {
+            let has = |r: &::std::option::Option<::std::string::String>| r.as_ref().is_some_and(|r| roles.contains(r));
+            let mut caps = ::std::vec::Vec::new();
+            if roles.contains(&fallback.staff) { caps.extend(["propose:*", "approve:*", "cancel", "link"].map(::std::string::String::from)); }
+            if has(&fallback.emergency) { caps.push("emergency".into()); }
+            if has(&fallback.senior) { caps.push("senior".into()); }
+            caps
+        } else {
+            roles.iter().filter_map(|r| matrix.get(r)).flatten().cloned().collect()
+        };
+        caps.sort();
+        caps.dedup();
+        Capabilities(caps)
+    }
+
+    pub fn lang(&self, tenant: &str, platform: &Platform) -> &str {
+        self.space(tenant, platform).and_then(|s| s.locale.as_deref())
+            .unwrap_or(match platform { Platform::Discord => &self.locale.discord, Platform::Stoat => &self.locale.stoat })
     }
 }
\ No newline at end of file
@@ -1,77 +1,769 @@
 use super::{models::*, ports::*};
-use crate::config::AppConfig;
+use crate::config::{AppConfig, CancelPolicy};
 use ::std::sync::Arc;
 use ::std::time::{SystemTime, UNIX_EPOCH};
 
 pub struct Core {
-    store: Arc<dyn StateStore>,
+    stores: ::std::collections::HashMap<::std::string::String, Arc<dyn StateStore>>,
     drivers: ::std::vec::Vec<Arc<dyn Driver>>,
     config: Arc<AppConfig>,
 }
 
 impl Core {
-    pub fn new(store: Arc<dyn StateStore>, drivers: ::std::vec::Vec<Arc<dyn Driver>>, config: Arc<AppConfig>) -> Self {
-        Self { store, drivers, config }
+    pub fn new(stores: ::std::collections::HashMap<::std::string::String, Arc<dyn StateStore>>, drivers: ::std::vec::Vec<Arc<dyn Driver>>, config: Arc<AppConfig>) -> Self {
+        Self { stores, drivers, config }
     }
 
-    pub async fn run(&self, event: Event) -> ::anyhow::Result<()> {
+    fn store(&self, tenant: &str) -> ::anyhow::Result<&Arc<dyn StateStore>> {
+        self.stores.get(tenant).ok_or_else(|| ::anyhow::anyhow!("Unknown tenant {}.", tenant))
+    }
+
+    pub async fn run(&self, event: Event) -> ::anyhow::Result<::std::string::String> {
         match event {
-            Event::Propose { action, target, author, origin, channel, reason } => {
-                let clean_target = target.replace("<@", "").replace(">", "").replace("!", "");
-                let is_discord = clean_target.chars().all(|c| c.is_ascii_digit()) && clean_target.len() >= 17;
-                let is_stoat = clean_target.len() == 26 && clean_target.chars().all(|c| c.is_ascii_alphanumeric());
-
-                let id = Identity {
-                    raw: clean_target.clone(),
-                    discord: if is_discord || origin == Platform::Discord { Some(clean_target.clone()) } else { None },
-                    stoat: if is_stoat || origin == Platform::Stoat { Some(clean_target.clone()) } else { None },
-                };
-                
+            Event::Propose(Draft { tenant, action, target, batch, role, author, origin, channel, reason, mut evidence, duration, delete_days, emergency, appeal, caps }) => {
+                if delete_days > 7 { ::anyhow::bail!(self.error(&tenant, &origin, "delete_days", &[])); }
+                if delete_days > 0 && action != "ban" { ::anyhow::bail!(self.error(&tenant, &origin, "delete_action", &[])); }
+                if delete_days > 0 && self.config.space(&tenant, &Platform::Discord).is_none() { ::anyhow::bail!(self.error(&tenant, &origin, "delete_platform", &[])); }
+                if duration.is_some() && action != "ban" { ::anyhow::bail!(self.error(&tenant, &origin, "duration_action", &[])); }
+                if reason.chars().count() > REASON_MAX as usize || evidence.chars().count() > REASON_MAX as usize { ::anyhow::bail!(self.error(&tenant, &origin, "too_long", &[("max", &REASON_MAX.to_string())])); }
+                if ROLE_ACTIONS.contains(&action.as_str()) && role.is_none() { ::anyhow::bail!(self.error(&tenant, &origin, "role_missing", &[("action", &action)])); }
+                if !batch.is_empty() && action != "ban" && action != "kick" { ::anyhow::bail!(self.error(&tenant, &origin, "bulk_action", &[])); }
+                if emergency && action != "ban" && action != "kick" { ::anyhow::bail!(self.error(&tenant, &origin, "emergency_action", &[])); }
+                if (action == "appeal") != appeal.is_some() { ::anyhow::bail!(self.error(&tenant, &origin, "appeal_case", &[])); }
+                if emergency && !caps.allows("emergency") { ::anyhow::bail!(self.error(&tenant, &origin, "emergency_role", &[])); }
+                if appeal.is_none() && !emergency { self.permit(&tenant, &origin, &caps, &format!("propose:{}", action))?; }
+                let store = self.store(&tenant)?;
+                if SERVER_ACTIONS.contains(&action.as_str()) {
+                    if store.get("server").await?.is_some() { ::anyhow::bail!(self.error(&tenant, &origin, "server_open", &[])); }
+                    let mut locked = false;
+                    for d in &self.drivers { locked |= store.snapshot(&format!("lockdown:{:?}", d.platform())).await?.is_some(); }
+                    if action == "lockdown" && locked { ::anyhow::bail!(self.error(&tenant, &origin, "locked", &[])); }
+                    if action == "unlock" && !locked { ::anyhow::bail!(self.error(&tenant, &origin, "not_locked", &[])); }
+                }
+
+                let mut resolved = ::std::vec::Vec::new();
+                for t in &batch { resolved.push(self.link(store, Identity::resolve(t, &origin)).await?); }
+                let mut batch = resolved;
+                batch.sort_by(|a, b| a.raw.cmp(&b.raw));
+                batch.dedup_by(|a, b| a.raw == b.raw);
                 let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-                let p = Proposal { target: id, action, reason, author, origin, channel, approvers: ::std::vec::Vec::new(), timestamp: ts };
-                
-                self.store.save(p.clone()).await?;
+                let mut bulk = None;
+                let id = if let Some(n) = appeal {
+                    let Some(orig) = store.case(n).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_case", &[("case", &n.to_string())])); };
+                    if orig.action != "ban" || !matches!(orig.status.as_deref(), Some("Executed" | "Partial" | "Upheld")) { ::anyhow::bail!(self.error(&tenant, &origin, "not_ban", &[("case", &n.to_string())])); }
+                    let Some(subject) = orig.subjects().into_iter().find(|i| [Some(&i.raw), i.discord.as_ref(), i.stoat.as_ref()].contains(&Some(&target))).cloned() else { ::anyhow::bail!(self.error(&tenant, &origin, "not_yours", &[("case", &n.to_string())])); };
+                    if store.get(&subject.raw).await?.is_some() { ::anyhow::bail!(self.error(&tenant, &origin, "appeal_open", &[])); }
+                    if evidence.is_empty() { evidence = orig.evidence; }
+                    subject
+                } else if SERVER_ACTIONS.contains(&action.as_str()) {
+                    Identity { raw: "server".into(), discord: None, stoat: None }
+                } else if !batch.is_empty() {
+                    let n = store.next_case().await?;
+                    bulk = Some(n);
+                    Identity { raw: format!("bulk-{}", n), discord: None, stoat: None }
+                } else {
+                    self.link(store, Identity::resolve(&target, &origin)).await?
+                };
+                if id.raw.is_empty() { ::anyhow::bail!(self.error(&tenant, &origin, "no_target", &[])); }
+                let open = store.get(&id.raw).await?;
+                if let Some(o) = open.as_ref().filter(|o| !emergency || o.emergency) { ::anyhow::bail!(self.error(&tenant, &origin, "duplicate", &[("case", &o.case.to_string()), ("target", &id.raw)])); }
+                let priors = if batch.is_empty() { self.priors(store, &id).await? } else { ::std::vec::Vec::new() };
+                let case = match bulk { Some(n) => n, None => store.next_case().await? };
+                let mut failures = ::std::vec::Vec::new();
+                let mut p = Proposal { tenant, case, target: id, batch, action, reason, evidence, duration, delete_days, emergency, role, priors, appeal, author, origin, channel, approvers: ::std::vec::Vec::new(), rejects: ::std::vec::Vec::new(), abstains: ::std::vec::Vec::new(), timestamp: ts, posts: ::std::vec::Vec::new(), discussion: ::std::vec::Vec::new(), status: None, closed_by: None };
+
+                if let Some(o) = open {
+                    self.audit(&o, format!("superseded by EMERGENCY case #{}", p.case)).await?;
+                    self.close(&Proposal { tenant: p.tenant.clone(), ..o }, "Superseded", None).await?;
+                }
+                if p.emergency {
+                    ::tracing::warn!("EMERGENCY {} on {} by {}: {}", p.action, p.target.raw, p.author, p.reason);
+                    self.audit(&p, format!("EMERGENCY {} executed by {}: {}", p.action, p.author, p.reason)).await?;
+                    self.forewarn(&p).await?;
+                    let (done, errors) = self.perform(&p, "EMERGENCY").await?;
+                    let Some(done) = done else {
+                        self.audit(&p, format!("EMERGENCY execution failed: {}", errors.join("; "))).await?;
+                        p.status = Some("Failed".into());
+                        self.store(&p.tenant)?.archive(&p).await?;
+                        ::anyhow::bail!(self.error(&p.tenant, &p.origin, "failed", &[("failures", &errors.join("; "))]));
+                    };
+                    if !errors.is_empty() { self.audit(&p, format!("EMERGENCY execution partly failed: {}", errors.join("; "))).await?; }
+                    p = done;
+                    failures = errors;
+                    for d in &self.drivers {
+                        if let Err(e) = d.alert(&p, &self.text(&d.platform(), "engine.alert_emergency", &p, &[])).await {
+                            ::tracing::error!("Driver Alert Error: {}", e);
+                        }
+                    }
+                    self.record(&p).await?;
+                    self.schedule(&p).await?;
+                } else {
+                    self.audit(&p, format!("{} proposed by {}: {}", p.action, p.author, p.reason)).await?;
+                }
+
                 for d in &self.drivers {
-                    if let Err(e) = d.notify(&p).await { ::tracing::error!("Driver Notify Error: {}", e); }
+                    match d.notify(&p).await {
+                        Ok(Some(post)) => p.posts.push((d.platform(), post)),
+                        Ok(None) => {}
+                        Err(e) => ::tracing::error!("Driver Notify Error: {}", e),
+                    }
+                }
+                let store = self.store(&p.tenant)?;
+                for (_, post) in &p.posts { store.index(post.rsplit(':').next().unwrap_or(post), Some((p.case, &p.target.raw))).await?; }
+                store.save(p.clone()).await?;
+                match p.batch.len() {
+                    _ if p.emergency && !failures.is_empty() => Ok(self.text(&p.origin, "engine.emergency_partial", &p, &[("failures", &failures.join("; "))])),
+                    _ if p.emergency => Ok(self.text(&p.origin, "engine.emergency", &p, &[])),
+                    0 => Ok(self.text(&p.origin, "engine.proposed", &p, &[])),
+                    n => Ok(self.text(&p.origin, "engine.proposed_bulk", &p, &[("count", &n.to_string())])),
                 }
             }
-            Event::Approve { target, approver } => {
-                if let Some(mut p) = self.store.get(&target).await? {
-                    if p.author == approver { ::anyhow::bail!("Self-approval rejected."); }
-                    if !p.approvers.contains(&approver) { p.approvers.push(approver.clone()); }
+            Event::Approve { tenant, target, approver, origin, caps } => {
+                let Some(mut p) = self.find(&tenant, &target).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)])); };
+                let me = self.aliases(&tenant, &approver).await?;
+                if p.approvers.iter().any(|v| me.contains(v)) { ::anyhow::bail!(self.error(&tenant, &origin, "approved_already", &[])); }
+                self.eligible(&p, &me, &origin, &caps).await?;
+                p.rejects.retain(|v| !me.contains(v));
+                p.abstains.retain(|v| !me.contains(v));
+                p.approvers.push(approver.clone());
+                self.audit(&p, format!("approved by {}", approver)).await?;
 
-                    if p.approvers.len() >= self.config.required_approvals {
-                        for d in &self.drivers {
-                            if let Err(e) = d.execute(&p, &approver).await { ::tracing::error!("Driver Execute Error: {}", e); }
+                if p.approvers.len() < self.config.quorum(&p.tenant) {
+                    let msg = self.text(&origin, "engine.approved", &p, &[("count", &p.approvers.len().to_string()), ("required", &self.config.quorum(&p.tenant).to_string())]);
+                    self.store(&p.tenant)?.save(p).await?;
+                    return Ok(msg);
+                }
+                if p.emergency {
+                    for d in &self.drivers {
+                        if let Err(e) = d.alert(&p, &self.text(&d.platform(), "engine.alert_upheld", &p, &[])).await {
+                            ::tracing::error!("Driver Alert Error: {}", e);
                         }
-                        self.store.remove(&target).await?;
-                    } else {
-                        self.store.save(p).await?;
                     }
+                    self.audit(&p, "EMERGENCY review upheld".into()).await?;
+                    self.settle(p.clone(), "Upheld").await?;
+                    return Ok(self.text(&origin, "engine.upheld", &p, &[]));
+                }
+                self.forewarn(&p).await?;
+                let (done, failures) = self.perform(&p, &approver).await?;
+                let Some(done) = done else {
+                    self.audit(&p, format!("execution failed: {}", failures.join("; "))).await?;
+                    self.close(&p, "Failed", None).await?;
+                    ::anyhow::bail!(self.error(&p.tenant, &origin, "failed", &[("failures", &failures.join("; "))]));
+                };
+                self.record(&done).await?;
+                self.schedule(&done).await?;
+                if failures.is_empty() {
+                    self.audit(&done, "executed".into()).await?;
+                    self.settle(done.clone(), "Executed").await?;
+                    return Ok(self.text(&origin, "engine.executed", &done, &[]));
                 }
+                self.audit(&done, format!("partially executed, failed: {}", failures.join("; "))).await?;
+                self.settle(done.clone(), "Partial").await?;
+                Ok(self.text(&origin, "engine.partial", &done, &[("failures", &failures.join("; "))]))
             }
-            Event::Cancel { target, author } => {
-                if let Some(p) = self.store.get(&target).await? {
-                    for d in &self.drivers {
-                        if let Err(e) = d.discard(&p, &format!("Cancelled by {}", author)).await {
-                            ::tracing::error!("Driver Discard Error: {}", e);
-                        }
+            Event::Cancel { tenant, target, author, origin, caps } => {
+                let Some(p) = self.find(&tenant, &target).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)])); };
+                let own = self.aliases(&tenant, &author).await?.contains(&p.author);
+                if !own { self.permit(&tenant, &origin, &caps, "cancel")?; }
+                match self.config.cancel_policy(&p.tenant, &p.action) {
+                    CancelPolicy::Author if !own => ::anyhow::bail!(self.error(&tenant, &origin, "cancel_author", &[("action", &p.action)])),
+                    CancelPolicy::Senior if !own && !caps.allows("senior") => ::anyhow::bail!(self.error(&tenant, &origin, "cancel_senior", &[("action", &p.action)])),
+                    CancelPolicy::Quorum if !own => return Box::pin(self.run(Event::Reject { tenant, target, voter: author, origin, caps })).await,
+                    _ => {}
+                }
+                self.close(&p, "Cancelled", Some(&author)).await?;
+                match p.emergency {
+                    true => Ok(self.text(&origin, "engine.reverted", &p, &[])),
+                    false => Ok(self.text(&origin, "engine.cancelled", &p, &[])),
+                }
+            }
+            Event::Reject { tenant, target, voter, origin, caps } => {
+                let Some(mut p) = self.find(&tenant, &target).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)])); };
+                let me = self.aliases(&tenant, &voter).await?;
+                if p.rejects.iter().any(|v| me.contains(v)) { ::anyhow::bail!(self.error(&tenant, &origin, "rejected_already", &[])); }
+                self.eligible(&p, &me, &origin, &caps).await?;
+                p.approvers.retain(|v| !me.contains(v));
+                p.abstains.retain(|v| !me.contains(v));
+                p.rejects.push(voter.clone());
+                self.audit(&p, format!("rejected by {}", voter)).await?;
+
+                if p.rejects.len() < self.config.quorum(&p.tenant) {
+                    let msg = self.text(&origin, "engine.rejection", &p, &[("count", &p.rejects.len().to_string()), ("required", &self.config.quorum(&p.tenant).to_string())]);
+                    self.store(&p.tenant)?.save(p).await?;
+                    return Ok(msg);
+                }
+                self.close(&p, "Rejected", None).await?;
+                match p.emergency {
+                    true => Ok(self.text(&origin, "engine.reverted", &p, &[])),
+                    false => Ok(self.text(&origin, "engine.rejected", &p, &[])),
+                }
+            }
+            Event::Abstain { tenant, target, voter, origin, caps } => {
+                let Some(mut p) = self.find(&tenant, &target).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)])); };
+                let me = self.aliases(&tenant, &voter).await?;
+                if p.abstains.iter().any(|v| me.contains(v)) { ::anyhow::bail!(self.error(&tenant, &origin, "abstained_already", &[])); }
+                self.eligible(&p, &me, &origin, &caps).await?;
+                p.approvers.retain(|v| !me.contains(v));
+                p.rejects.retain(|v| !me.contains(v));
+                p.abstains.push(voter.clone());
+                self.audit(&p, format!("abstained by {}", voter)).await?;
+                let msg = self.text(&origin, "engine.abstained", &p, &[]);
+                self.store(&p.tenant)?.save(p).await?;
+                Ok(msg)
+            }
+            Event::Withdraw { tenant, target, voter, origin, vote } => {
+                let Some(mut p) = self.find(&tenant, &target).await? else {
+                    if vote.is_some() { return Ok(::std::string::String::new()); }
+                    ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)]));
+                };
+                let me = self.aliases(&tenant, &voter).await?;
+                let votes = p.approvers.len() + p.rejects.len() + p.abstains.len();
+                for (kind, list) in [(Vote::Approve, &mut p.approvers), (Vote::Reject, &mut p.rejects), (Vote::Abstain, &mut p.abstains)] {
+                    if vote.is_none_or(|v| v == kind) { list.retain(|v| !me.contains(v)); }
+                }
+                if p.approvers.len() + p.rejects.len() + p.abstains.len() == votes {
+                    if vote.is_some() { return Ok(::std::string::String::new()); }
+                    ::anyhow::bail!(self.error(&tenant, &origin, "not_voted", &[]));
+                }
+                self.audit(&p, format!("vote withdrawn by {}", voter)).await?;
+                let msg = self.text(&origin, "engine.withdrawn", &p, &[]);
+                self.store(&p.tenant)?.save(p).await?;
+                Ok(msg)
+            }
+            Event::Toggle { tenant, target, voter, origin, caps } => {
+                let me = self.aliases(&tenant, &voter).await?;
+                let voted = self.find(&tenant, &target).await?.is_some_and(|p| p.approvers.iter().any(|v| me.contains(v)));
+                Box::pin(self.run(match voted {
+                    true => Event::Withdraw { tenant, target, voter, origin, vote: Some(Vote::Approve) },
+                    false => Event::Approve { tenant, target, approver: voter, origin, caps },
+                })).await
+            }
+            Event::Amend { tenant, target, author, origin, action, reason, duration, caps } => {
+                let Some(mut p) = self.find(&tenant, &target).await? else { ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &target)])); };
+                if !self.aliases(&tenant, &author).await?.contains(&p.author) { ::anyhow::bail!(self.error(&tenant, &origin, "amend_author", &[])); }
+                if p.emergency { ::anyhow::bail!(self.error(&tenant, &origin, "amend_emergency", &[])); }
+                let mut changes = ::std::vec::Vec::new();
+                if let Some(action) = action.filter(|a| *a != p.action) {
+                    if !INFRACTION_ACTIONS.contains(&p.action.as_str()) || !INFRACTION_ACTIONS.contains(&action.as_str()) { ::anyhow::bail!(self.error(&tenant, &origin, "amend_action", &[])); }
+                    self.permit(&tenant, &origin, &caps, &format!("propose:{}", action))?;
+                    changes.push(format!("action {} -> {}", p.action, action));
+                    if action != "ban" { p.delete_days = 0; }
+                    if action != "ban" && duration.is_none() && p.duration.take().is_some() { changes.push("duration removed".into()); }
+                    p.action = action;
+                }
+                if let Some(reason) = reason.filter(|r| !r.is_empty() && *r != p.reason) {
+                    if reason.chars().count() > REASON_MAX as usize { ::anyhow::bail!(self.error(&tenant, &origin, "too_long", &[("max", &REASON_MAX.to_string())])); }
+                    changes.push(format!("reason \"{}\" -> \"{}\"", p.reason, reason));
+                    p.reason = reason;
+                }
+                if let Some(duration) = duration.filter(|d| Some(*d) != p.duration) {
+                    if p.action != "ban" { ::anyhow::bail!(self.error(&tenant, &origin, "duration_action", &[])); }
+                    changes.push(format!("duration {} -> {}", p.duration.map_or("none".into(), format_duration), format_duration(duration)));
+                    p.duration = Some(duration);
+                }
+                if changes.is_empty() { ::anyhow::bail!(self.error(&tenant, &origin, "amend_nothing", &[])); }
+                let votes = p.approvers.len() + p.rejects.len() + p.abstains.len();
+                p.approvers.clear();
+                p.rejects.clear();
+                p.abstains.clear();
+                self.audit(&p, format!("amended by {}: {}{}", author, changes.join(", "), if votes > 0 { format!("; {} vote(s) reset", votes) } else { ::std::string::String::new() })).await?;
+                self.store(&p.tenant)?.save(p.clone()).await?;
+                for d in &self.drivers {
+                    let Some((_, post)) = p.posts.iter().find(|(platform, _)| *platform == d.platform()) else { continue; };
+                    if let Err(e) = d.refresh(&p, post).await { ::tracing::error!("Driver Refresh Error: {}", e); }
+                }
+                Ok(self.text(&origin, "engine.amended", &p, &[]))
+            }
+            Event::Link { tenant, discord, stoat, author, origin, caps } => {
+                self.permit(&tenant, &origin, &caps, "link")?;
+                let (discord, stoat) = (Identity::resolve(&discord, &Platform::Discord), Identity::resolve(&stoat, &Platform::Stoat));
+                let (Some(d), Some(s)) = (discord.discord.filter(|_| discord.raw.len() >= 17 && discord.raw.chars().all(|c| c.is_ascii_digit())), stoat.stoat.filter(|_| stoat.raw.len() == 26)) else {
+                    ::anyhow::bail!(self.error(&tenant, &origin, "link_ids", &[]));
+                };
+                let store = self.store(&tenant)?;
+                store.set_link(&d, &s).await?;
+                store.audit(&d, &format!("[{}] linked to Stoat {} by {}", SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(), s, author)).await?;
+                Ok(self.config.text(&tenant, &origin, "engine.linked", &[("discord", &d), ("stoat", &s)]))
+            }
+            Event::Comment { tenant, origin, author, text, refs, post } => {
+                let store = self.store(&tenant)?;
+                let mut open = None;
+                for r in &refs {
+                    let Some((case, key)) = store.indexed(r).await? else { continue; };
+                    open = store.get(&key).await?.filter(|p| p.case == case);
+                    if open.is_some() { break; }
+                }
+                let Some(mut p) = open else { return Ok(::std::string::String::new()); };
+                p.tenant = tenant;
+                let mut comment = Comment { origin, author, text, timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(), posts: vec![post] };
+                for d in self.drivers.iter().filter(|d| d.platform() != comment.origin) {
+                    match d.relay(&p, &comment).await {
+                        Ok(Some(id)) => comment.posts.push(id),
+                        Ok(None) => {}
+                        Err(e) => ::tracing::error!("Driver Relay Error: {}", e),
                     }
-                    self.store.remove(&target).await?;
                 }
+                for id in &comment.posts { store.index(id, Some((p.case, &p.target.raw))).await?; }
+                p.discussion.push(comment);
+                store.save(p).await?;
+                Ok(::std::string::String::new())
+            }
+            Event::Pending { tenant, reply } => {
+                let mut open = self.store(&tenant)?.list().await?;
+                open.sort_by_key(|p| p.case);
+                for p in &mut open { p.tenant = tenant.clone(); }
+                let _ = reply.send(open);
+                Ok(::std::string::String::new())
+            }
+            Event::History { tenant, target, origin, trail } => {
+                let store = self.store(&tenant)?;
+                let id = self.link(store, Identity::resolve(&target, &origin)).await?;
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let record = self.priors(store, &id).await?;
+                let mut out = if record.is_empty() { self.config.text(&tenant, &origin, "infraction.none", &[("target", &id.raw)]) } else {
+                    let lines: ::std::vec::Vec<_> = record.iter().map(|i| format!("• {}", self.config.templates.infraction(self.config.lang(&tenant, &origin), &origin, i, now))).collect();
+                    format!("{}\n{}", self.config.text(&tenant, &origin, "infraction.header", &[("target", &id.raw), ("count", &record.len().to_string())]), lines.join("\n"))
+                };
+                if trail { out.push_str(&self.trail(&tenant, &id.raw, None, &origin).await?); }
+                Ok(out)
+            }
+            Event::Case { tenant, reference, origin, trail } => {
+                let p = match self.find(&tenant, &reference).await? {
+                    Some(p) => p,
+                    None => match case_number(&reference) {
+                        Some(n) => self.store(&tenant)?.case(n).await?.ok_or_else(|| ::anyhow::anyhow!(self.error(&tenant, &origin, "no_case", &[("case", &n.to_string())])))?,
+                        None => ::anyhow::bail!(self.error(&tenant, &origin, "no_proposal", &[("target", &reference)])),
+                    },
+                };
+                let mut out = self.describe(&p, &origin);
+                if trail { out.push_str(&self.trail(&tenant, &p.target.raw, Some(p.case), &origin).await?); }
+                Ok(out)
             }
             Event::Sweep => {
                 let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-                for p in self.store.list().await? {
-                    if now > p.timestamp + self.config.expiry_seconds {
+                for (tenant, store) in &self.stores {
+                    for mut p in store.expired(now).await? {
+                        p.tenant = tenant.clone();
                         for d in &self.drivers {
-                            let _ = d.discard(&p, "Expired").await;
+                            if let Err(e) = d.revert(&p).await { ::tracing::error!("Driver Revert Error: {}", e); }
+                        }
+                        self.audit(&p, format!("temporary ban lifted after {}", p.duration.map_or("?".into(), format_duration))).await?;
+                    }
+                    for mut p in store.list().await? {
+                        p.tenant = tenant.clone();
+                        if now > p.timestamp + self.config.expiry_seconds {
+                            self.close(&p, "Expired", None).await?;
                         }
-                        self.store.remove(&p.target.raw).await?;
                     }
                 }
+                Ok(::std::string::String::new())
+            }
+        }
+    }
+
+    async fn link(&self, store: &Arc<dyn StateStore>, mut id: Identity) -> ::anyhow::Result<Identity> {
+        match (&id.discord, &id.stoat) {
+            (Some(d), None) => id.stoat = store.linked(&Platform::Discord, d).await?,
+            (None, Some(s)) => id.discord = store.linked(&Platform::Stoat, s).await?,
+            _ => {}
+        }
+        Ok(id)
+    }
+
+    async fn aliases(&self, tenant: &str, actor: &str) -> ::anyhow::Result<::std::vec::Vec<::std::string::String>> {
+        let mut me = vec![actor.to_string()];
+        if let Some((platform, id)) = Platform::parse_actor(actor) {
+            if let Some(other) = self.store(tenant)?.linked(&platform, id).await? { me.push(platform.other().actor(&other)); }
+        }
+        Ok(me)
+    }
+
+    async fn priors(&self, store: &Arc<dyn StateStore>, id: &Identity) -> ::anyhow::Result<::std::vec::Vec<Infraction>> {
+        let mut keys = vec![&id.raw];
+        keys.extend(id.discord.iter().chain(id.stoat.iter()).filter(|k| **k != id.raw));
+        keys.dedup();
+        let mut all = ::std::vec::Vec::new();
+        for k in keys { all.extend(store.infractions(k).await?); }
+        all.sort_by_key(|i| i.timestamp);
+        Ok(all)
+    }
+
+    async fn apply(&self, d: &Arc<dyn Driver>, p: &Proposal, approver: &str) -> ::anyhow::Result<Report> {
+        let key = format!("lockdown:{:?}", d.platform());
+        let mut report = Report::new();
+        match p.action.as_str() {
+            "lockdown" => if let Some(snap) = d.lockdown(p).await? { self.store(&p.tenant)?.set_snapshot(&key, Some(&snap)).await?; report.push((p.target.raw.clone(), Ok(()))); },
+            "unlock" => if let Some(snap) = self.store(&p.tenant)?.snapshot(&key).await? { d.restore(p, &snap).await?; self.store(&p.tenant)?.set_snapshot(&key, None).await?; report.push((p.target.raw.clone(), Ok(()))); },
+            _ => {}
+        }
+        report.extend(d.execute(p, approver).await?);
+        Ok(report)
+    }
+
+    async fn perform(&self, p: &Proposal, approver: &str) -> ::anyhow::Result<(::std::option::Option<Proposal>, ::std::vec::Vec<::std::string::String>)> {
+        let (mut applied, mut failures) = (::std::vec::Vec::new(), ::std::vec::Vec::new());
+        for d in &self.drivers {
+            match self.apply(d, p, approver).await {
+                Ok(report) => for (subject, res) in report {
+                    match res { Ok(()) => applied.push(subject), Err(e) => failures.push(format!("{:?}: {}", d.platform(), e)) }
+                },
+                Err(e) => {
+                    ::tracing::error!("Driver Execute Error: {}", e);
+                    failures.push(format!("{:?}: {}", d.platform(), e));
+                }
+            }
+        }
+        if p.action == "lockdown" && applied.is_empty() && failures.is_empty() { failures.push(self.error(&p.tenant, &p.origin, "lockdown_empty", &[])); }
+        if failures.is_empty() { return Ok((Some(p.clone()), failures)); }
+        if applied.is_empty() { return Ok((None, failures)); }
+        let mut done = p.clone();
+        done.batch.retain(|i| applied.contains(&i.raw));
+        Ok((Some(done), failures))
+    }
+
+    async fn eligible(&self, p: &Proposal, me: &[::std::string::String], origin: &Platform, caps: &Capabilities) -> ::anyhow::Result<()> {
+        self.permit(&p.tenant, origin, caps, &format!("approve:{}", p.action))?;
+        if me.iter().any(|v| v == SYSTEM_AUTHOR) { ::anyhow::bail!(self.error(&p.tenant, origin, "system_vote", &[])); }
+        if me.contains(&p.author) { ::anyhow::bail!(self.error(&p.tenant, origin, "own_vote", &[])); }
+        if let Some(orig) = match p.appeal { Some(n) => self.store(&p.tenant)?.case(n).await?, None => None } {
+            if me.contains(&orig.author) || orig.approvers.iter().any(|a| me.contains(a)) { ::anyhow::bail!(self.error(&p.tenant, origin, "appeal_vote", &[("case", &orig.case.to_string())])); }
+        }
+        Ok(())
+    }
+
+    async fn forewarn(&self, p: &Proposal) -> ::anyhow::Result<()> {
+        if !p.batch.is_empty() || !self.config.notice.enabled(&p.action) { return Ok(()); }
+        for d in &self.drivers {
+            let appeal = match p.action.as_str() {
+                "ban" if self.config.tenants.iter().filter(|t| t.space(&d.platform()).is_some()).count() > 1 => {
+                    self.text(&d.platform(), "notice.appeal_tenant", p, &[("prefix", &self.config.command_prefix), ("tenant", &p.tenant)])
+                }
+                "ban" => self.text(&d.platform(), "notice.appeal", p, &[("prefix", &self.config.command_prefix)]),
+                _ => ::std::string::String::new(),
+            };
+            let text = self.text(&d.platform(), "notice.body", p, &[("appeal", &appeal)]);
+            if let Err(e) = d.message_user(&p.target, text.trim_end()).await {
+                self.audit(p, format!("notice to {} failed on {:?}: {}", p.target.raw, d.platform(), e)).await?;
             }
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn record(&self, p: &Proposal) -> ::anyhow::Result<()> {
+        if !INFRACTION_ACTIONS.contains(&p.action.as_str()) { return Ok(()); }
+        let infraction = Infraction { action: p.action.clone(), reason: p.reason.clone(), author: p.author.clone(), approvers: p.approvers.clone(), timestamp: p.timestamp, duration: p.duration };
+        for id in p.subjects() { self.store(&p.tenant)?.record(&id.raw, &infraction).await?; }
+        Ok(())
+    }
+
+    async fn schedule(&self, p: &Proposal) -> ::anyhow::Result<()> {
+        if p.action != "ban" && p.action != "appeal" { return Ok(()); }
+        let duration = p.duration.filter(|_| p.action == "ban");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let until = duration.map(|d| now.saturating_add(d));
+        let store = self.store(&p.tenant)?;
+        for id in p.subjects() {
+            let single = if p.batch.is_empty() { p.clone() } else { Proposal { batch: vec![id.clone()], ..p.clone() } };
+            store.schedule(&id.raw, until.map(|t| (t, &single))).await?;
+        }
+        let Some(d) = duration else { return Ok(()); };
+        self.audit(p, format!("ban expires in {}", format_duration(d))).await
+    }
+
+    async fn close(&self, p: &Proposal, status: &str, by: ::std::option::Option<&str>) -> ::anyhow::Result<()> {
+        let p = Proposal { status: Some(status.into()), closed_by: by.map(::std::string::String::from), ..p.clone() };
+        let reason = match by { Some(a) => format!("{} by {}", status, a), None => status.to_string() };
+        for d in &self.drivers {
+            if let Err(e) = d.discard(&p, &self.status(&d.platform(), &p)).await { ::tracing::error!("Driver Discard Error: {}", e); }
+        }
+        if p.emergency {
+            ::tracing::warn!("EMERGENCY {} on {} reverted: {}", p.action, p.target.raw, reason);
+            for id in p.subjects() { self.store(&p.tenant)?.schedule(&id.raw, None).await?; }
+            for d in &self.drivers {
+                if let Err(e) = d.revert(&p).await { ::tracing::error!("Driver Revert Error: {}", e); }
+                if let Err(e) = d.alert(&p, &self.text(&d.platform(), "engine.alert_reverted", &p, &[("status", &self.status(&d.platform(), &p))])).await {
+                    ::tracing::error!("Driver Alert Error: {}", e);
+                }
+            }
+            self.audit(&p, format!("EMERGENCY review failed, reverted: {}", reason)).await?;
+        } else {
+            self.audit(&p, format!("closed: {}", reason)).await?;
+        }
+        self.settle(p, status).await
+    }
+
+    async fn find(&self, tenant: &str, reference: &str) -> ::anyhow::Result<::std::option::Option<Proposal>> {
+        let store = self.store(tenant)?;
+        let found = match case_number(reference) {
+            Some(n) => store.list().await?.into_iter().find(|p| p.case == n),
+            None => store.get(&Identity::resolve(reference, &Platform::Discord).raw).await?,
+        };
+        Ok(found.map(|p| Proposal { tenant: tenant.into(), ..p }))
+    }
+
+    async fn settle(&self, mut p: Proposal, status: &str) -> ::anyhow::Result<()> {
+        p.status = Some(status.into());
+        if !p.discussion.is_empty() {
+            let transcript: ::std::vec::Vec<_> = p.discussion.iter().map(|c| format!("[{}] {} ({:?}): {}", c.timestamp, c.author, c.origin, c.text)).collect();
+            self.audit(&p, format!("discussion transcript:\n{}", transcript.join("\n"))).await?;
+        }
+        let store = self.store(&p.tenant)?;
+        for post in p.posts.iter().map(|(_, x)| x.rsplit(':').next().unwrap_or(x)).chain(p.discussion.iter().flat_map(|c| c.posts.iter().map(::std::string::String::as_str))) {
+            store.index(post, None).await?;
+        }
+        store.archive(&p).await?;
+        store.remove(&p.target.raw).await
+    }
+
+    async fn trail(&self, tenant: &str, key: &str, case: ::std::option::Option<u64>, origin: &Platform) -> ::anyhow::Result<::std::string::String> {
+        let entries: ::std::vec::Vec<_> = self.store(tenant)?.trail(key).await?.into_iter().filter(|e| case.is_none_or(|n| e.contains(&format!("] #{} ", n)))).collect();
+        if entries.is_empty() { return Ok(::std::string::String::new()); }
+        let shown: ::std::vec::Vec<_> = entries[entries.len().saturating_sub(10)..].iter().map(|e| format!("• {}", e)).collect();
+        Ok(format!("\n{}\n{}", self.config.text(tenant, origin, "case.history", &[("count", &entries.len().to_string())]), shown.join("\n")))
+    }
+
+    fn describe(&self, p: &Proposal, origin: &Platform) -> ::std::string::String {
+        let t = |key: &str, vars: &[(&str, &str)]| self.text(origin, key, p, vars);
+        let target = match p.batch.len() { 0 => p.target.raw.clone(), n => t("case.batch", &[("count", &n.to_string())]) };
+        let status = self.status(origin, p);
+        let mut out = t("case.summary", &[("target", &target), ("status", &status)]);
+        let mut line = |label: &str, value: ::std::string::String| out.push_str(&format!("\n{}: {}", t(label, &[]), value));
+        let who = |list: &[::std::string::String]| list.iter().map(|a| origin.mention(a)).collect::<::std::vec::Vec<_>>().join(", ");
+        line("proposal.requester", origin.mention(&p.author));
+        line("proposal.reason", p.reason.clone());
+        line("proposal.approvals", match p.approvers.len() {
+            0 => format!("0/{}", self.config.quorum(&p.tenant)),
+            n => format!("{}/{} ({})", n, self.config.quorum(&p.tenant), who(&p.approvers)),
+        });
+        if !p.rejects.is_empty() { line("proposal.rejections", format!("{}/{} ({})", p.rejects.len(), self.config.quorum(&p.tenant), who(&p.rejects))); }
+        if !p.abstains.is_empty() { line("proposal.abstentions", who(&p.abstains)); }
+        if let Some(d) = p.duration { line("proposal.duration", format_duration(d)); }
+        if let Some(r) = &p.role { line("proposal.role", r.clone()); }
+        if let Some(n) = p.appeal { line("proposal.appeal_of", t("proposal.appeal_case", &[("appeal", &n.to_string())])); }
+        if !p.evidence.is_empty() { line("proposal.evidence", p.evidence.clone()); }
+        if p.emergency { out.push_str(&format!("\n{}", t("case.emergency", &[]))); }
+        out
+    }
+
+    fn status(&self, platform: &Platform, p: &Proposal) -> ::std::string::String {
+        let key = match p.status.as_deref() {
+            None => "case.open",
+            Some("Executed") => "status.executed",
+            Some("Partial") => "status.partial",
+            Some("Failed") => "status.failed",
+            Some("Upheld") => "status.upheld",
+            Some("Expired") => "status.expired",
+            Some("Cancelled") => "status.cancelled",
+            Some("Rejected") => "status.rejected",
+            Some("Superseded") => "status.superseded",
+            Some(other) => return other.into(),
+        };
+        let by = p.closed_by.as_deref().map_or_else(::std::string::String::new, |a| platform.mention(a));
+        let voters = p.rejects.iter().map(|a| platform.mention(a)).collect::<::std::vec::Vec<_>>().join(", ");
+        self.text(platform, key, p, &[("by", &by), ("voters", &voters)])
+    }
+
+    fn text(&self, platform: &Platform, key: &str, p: &Proposal, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.templates.proposal(self.config.lang(&p.tenant, platform), platform, key, p, vars)
+    }
+
+    fn error(&self, tenant: &str, origin: &Platform, key: &str, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.text(tenant, origin, &format!("error.{}", key), vars)
+    }
+
+    fn permit(&self, tenant: &str, origin: &Platform, caps: &Capabilities, cap: &str) -> ::anyhow::Result<()> {
+        if !caps.allows(cap) { ::anyhow::bail!(self.error(tenant, origin, "capability", &[("cap", cap)])); }
+        Ok(())
+    }
+
+    async fn audit(&self, p: &Proposal, entry: ::std::string::String) -> ::anyhow::Result<()> {
+        let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        ::tracing::info!("Audit [{}] #{}: {}", p.target.raw, p.case, entry);
+        self.store(&p.tenant)?.audit(&p.target.raw, &format!("[{}] #{} {}", ts, p.case, entry)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::std::collections::HashMap;
+    use ::std::sync::Mutex;
+
+    const TARGET: &str = "100000000000000001";
+
+    #[derive(Default)]
+    struct Memory {
+        proposals: Mutex<HashMap<::std::string::String, Proposal>>,
+        cases: Mutex<HashMap<u64, Proposal>>,
+        links: Mutex<HashMap<::std::string::String, ::std::string::String>>,
+        next: Mutex<u64>,
+    }
+
+    #[::async_trait::async_trait]
+    impl StateStore for Memory {
+        async fn save(&self, p: Proposal) -> ::anyhow::Result<()> { self.proposals.lock().unwrap().insert(p.target.raw.clone(), p); Ok(()) }
+        async fn get(&self, target: &str) -> ::anyhow::Result<::std::option::Option<Proposal>> { Ok(self.proposals.lock().unwrap().get(target).cloned()) }
+        async fn remove(&self, target: &str) -> ::anyhow::Result<()> { self.proposals.lock().unwrap().remove(target); Ok(()) }
+        async fn list(&self) -> ::anyhow::Result<::std::vec::Vec<Proposal>> { Ok(self.proposals.lock().unwrap().values().cloned().collect()) }
+        async fn next_case(&self) -> ::anyhow::Result<u64> { let mut n = self.next.lock().unwrap(); *n += 1; Ok(*n) }
+        async fn archive(&self, p: &Proposal) -> ::anyhow::Result<()> { self.cases.lock().unwrap().insert(p.case, p.clone()); Ok(()) }
+        async fn case(&self, case: u64) -> ::anyhow::Result<::std::option::Option<Proposal>> { Ok(self.cases.lock().unwrap().get(&case).cloned()) }
+        async fn audit(&self, _: &str, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+        async fn trail(&self, _: &str) -> ::anyhow::Result<::std::vec::Vec<::std::string::String>> { Ok(::std::vec::Vec::new()) }
+        async fn record(&self, _: &str, _: &Infraction) -> ::anyhow::Result<()> { Ok(()) }
+        async fn infractions(&self, _: &str) -> ::anyhow::Result<::std::vec::Vec<Infraction>> { Ok(::std::vec::Vec::new()) }
+        async fn set_link(&self, discord: &str, stoat: &str) -> ::anyhow::Result<()> {
+            let mut links = self.links.lock().unwrap();
+            links.insert(format!("discord:{}", discord), stoat.into());
+            links.insert(format!("stoat:{}", stoat), discord.into());
+            Ok(())
+        }
+        async fn linked(&self, platform: &Platform, id: &str) -> ::anyhow::Result<::std::option::Option<::std::string::String>> { Ok(self.links.lock().unwrap().get(&platform.actor(id)).cloned()) }
+        async fn snapshot(&self, _: &str) -> ::anyhow::Result<::std::option::Option<::std::string::String>> { Ok(None) }
+        async fn set_snapshot(&self, _: &str, _: ::std::option::Option<&str>) -> ::anyhow::Result<()> { Ok(()) }
+        async fn index(&self, _: &str, _: ::std::option::Option<(u64, &str)>) -> ::anyhow::Result<()> { Ok(()) }
+        async fn indexed(&self, _: &str) -> ::anyhow::Result<::std::option::Option<(u64, ::std::string::String)>> { Ok(None) }
+        async fn schedule(&self, _: &str, _: ::std::option::Option<(u64, &Proposal)>) -> ::anyhow::Result<()> { Ok(()) }
+        async fn expired(&self, _: u64) -> ::anyhow::Result<::std::vec::Vec<Proposal>> { Ok(::std::vec::Vec::new()) }
+    }
+
+    struct Noop;
+
+    #[::async_trait::async_trait]
+    impl Driver for Noop {
+        fn platform(&self) -> Platform { Platform::Discord }
+        async fn notify(&self, _: &Proposal) -> ::anyhow::Result<::std::option::Option<::std::string::String>> { Ok(None) }
+        async fn refresh(&self, _: &Proposal, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+        async fn execute(&self, _: &Proposal, _: &str) -> ::anyhow::Result<Report> { Ok(Report::new()) }
+        async fn discard(&self, _: &Proposal, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+        async fn revert(&self, _: &Proposal) -> ::anyhow::Result<()> { Ok(()) }
+        async fn alert(&self, _: &Proposal, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+        async fn relay(&self, _: &Proposal, _: &Comment) -> ::anyhow::Result<::std::option::Option<::std::string::String>> { Ok(None) }
+        async fn message_user(&self, _: &Identity, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+        async fn lockdown(&self, _: &Proposal) -> ::anyhow::Result<::std::option::Option<::std::string::String>> { Ok(None) }
+        async fn restore(&self, _: &Proposal, _: &str) -> ::anyhow::Result<()> { Ok(()) }
+    }
+
+    fn core() -> (Core, Arc<Memory>) {
+        let config: AppConfig = ::toml::from_str(r#"
+            required_approvals = 2
+            command_prefix = "/"
+            expiry_seconds = 86400
+            [cancel.actions]
+            kick = "author"
+            warn = "senior"
+            ban = "quorum"
+            [[tenants]]
+            name = "t"
+            [tenants.discord]
+            id = "1"
+            log = "2"
+            staff = "3"
+        "#).unwrap();
+        let store = Arc::new(Memory::default());
+        let stores = HashMap::from([("t".to_string(), Arc::clone(&store) as Arc<dyn StateStore>)]);
+        (Core::new(stores, vec![Arc::new(Noop)], Arc::new(config)), store)
+    }
+
+    fn staff(id: &str) -> ::std::string::String {
+        Platform::Discord.actor(id)
+    }
+
+    fn caps(list: &[&str]) -> Capabilities {
+        Capabilities(list.iter().map(|c| c.to_string()).collect())
+    }
+
+    async fn propose(core: &Core, action: &str, author: &str) -> ::anyhow::Result<::std::string::String> {
+        core.run(Event::Propose(Draft { tenant: "t".into(), action: action.into(), target: TARGET.into(), author: staff(author), reason: "spam".into(), caps: Capabilities::all(), ..Default::default() })).await
+    }
+
+    fn approve(voter: &str) -> Event {
+        Event::Approve { tenant: "t".into(), target: TARGET.into(), approver: voter.into(), origin: Platform::Discord, caps: Capabilities::all() }
+    }
+
+    fn cancel(author: &str, caps: Capabilities) -> Event {
+        Event::Cancel { tenant: "t".into(), target: TARGET.into(), author: staff(author), origin: Platform::Discord, caps }
+    }
+
+    fn withdraw(voter: &str) -> Event {
+        Event::Withdraw { tenant: "t".into(), target: TARGET.into(), voter: voter.into(), origin: Platform::Discord, vote: None }
+    }
+
+    fn refused(res: ::anyhow::Result<::std::string::String>) -> ::std::string::String {
+        res.expect_err("expected a refusal").to_string()
+    }
+
+    #[::tokio::test]
+    async fn requester_cannot_vote_on_own_proposal() {
+        let (core, store) = core();
+        propose(&core, "ban", "10").await.unwrap();
+        store.set_link("10", "stoat10").await.unwrap();
+        assert_eq!(refused(core.run(approve(&staff("10"))).await), "error.own_vote");
+        assert_eq!(refused(core.run(approve("stoat:stoat10")).await), "error.own_vote");
+    }
+
+    #[::tokio::test]
+    async fn linked_accounts_vote_once() {
+        let (core, store) = core();
+        propose(&core, "ban", "10").await.unwrap();
+        store.set_link("11", "stoat11").await.unwrap();
+        assert_eq!(core.run(approve(&staff("11"))).await.unwrap(), "engine.approved");
+        assert_eq!(refused(core.run(approve("stoat:stoat11")).await), "error.approved_already");
+        assert_eq!(store.get(TARGET).await.unwrap().unwrap().approvers, vec![staff("11")]);
+    }
+
+    #[::tokio::test]
+    async fn appeal_excludes_original_participants() {
+        let (core, _) = core();
+        propose(&core, "ban", "10").await.unwrap();
+        core.run(approve(&staff("11"))).await.unwrap();
+        assert_eq!(core.run(approve(&staff("12"))).await.unwrap(), "engine.executed");
+        core.run(Event::Propose(Draft { tenant: "t".into(), action: "appeal".into(), target: TARGET.into(), author: staff(TARGET), reason: "sorry".into(), appeal: Some(1), ..Default::default() })).await.unwrap();
+        for participant in ["10", "11", "12"] {
+            assert_eq!(refused(core.run(approve(&staff(participant))).await), "error.appeal_vote");
+        }
+        assert_eq!(refused(core.run(approve(&staff(TARGET))).await), "error.own_vote");
+        assert_eq!(core.run(approve(&staff("13"))).await.unwrap(), "engine.approved");
+    }
+
+    #[::tokio::test]
+    async fn author_policy_limits_cancel_to_requester() {
+        let (core, store) = core();
+        propose(&core, "kick", "10").await.unwrap();
+        assert_eq!(refused(core.run(cancel("11", Capabilities::all())).await), "error.cancel_author");
+        assert_eq!(core.run(cancel("10", caps(&[]))).await.unwrap(), "engine.cancelled");
+        assert!(store.get(TARGET).await.unwrap().is_none());
+        assert_eq!(store.case(1).await.unwrap().unwrap().status.as_deref(), Some("Cancelled"));
+    }
+
+    #[::tokio::test]
+    async fn senior_policy_needs_senior_role() {
+        let (core, _) = core();
+        propose(&core, "warn", "10").await.unwrap();
+        assert_eq!(refused(core.run(cancel("11", caps(&["cancel"]))).await), "error.cancel_senior");
+        assert_eq!(refused(core.run(cancel("11", caps(&["senior"]))).await), "error.capability");
+        assert_eq!(core.run(cancel("11", caps(&["cancel", "senior"]))).await.unwrap(), "engine.cancelled");
+    }
+
+    #[::tokio::test]
+    async fn quorum_policy_turns_cancel_into_rejection() {
+        let (core, store) = core();
+        propose(&core, "ban", "10").await.unwrap();
+        assert_eq!(core.run(cancel("11", Capabilities::all())).await.unwrap(), "engine.rejection");
+        assert_eq!(store.get(TARGET).await.unwrap().unwrap().rejects, vec![staff("11")]);
+        assert_eq!(core.run(cancel("12", Capabilities::all())).await.unwrap(), "engine.rejected");
+        assert_eq!(store.case(1).await.unwrap().unwrap().status.as_deref(), Some("Rejected"));
+    }
+
+    #[::tokio::test]
+    async fn withdrawal_removes_vote_across_linked_accounts() {
+        let (core, store) = core();
+        propose(&core, "ban", "10").await.unwrap();
+        store.set_link("11", "stoat11").await.unwrap();
+        core.run(approve(&staff("11"))).await.unwrap();
+        assert_eq!(refused(core.run(withdraw(&staff("12"))).await), "error.not_voted");
+        assert_eq!(core.run(withdraw("stoat:stoat11")).await.unwrap(), "engine.withdrawn");
+        assert!(store.get(TARGET).await.unwrap().unwrap().approvers.is_empty());
+        assert_eq!(refused(core.run(withdraw(&staff("11"))).await), "error.not_voted");
+    }
+}
@@ -1,3 +1,5 @@
 pub mod models;
 pub mod ports;
-pub mod engine;
\ No newline at end of file
+pub mod engine;
+pub mod raid;
+pub mod templates;
\ No newline at end of file
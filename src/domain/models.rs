@@ -1,7 +1,33 @@
 use ::serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Platform { Discord, Stoat }
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Platform { #[default] Discord, Stoat }
+
+impl Platform {
+    pub fn other(&self) -> Self {
+        match self { Platform::Discord => Platform::Stoat, Platform::Stoat => Platform::Discord }
+    }
+
+    pub fn actor(&self, id: &str) -> ::std::string::String {
+        match self { Platform::Discord => format!("discord:{}", id), Platform::Stoat => format!("stoat:{}", id) }
+    }
+
+    pub fn parse_actor(actor: &str) -> ::std::option::Option<(Self, &str)> {
+        match actor.split_once(':')? {
+            ("discord", id) => Some((Platform::Discord, id)),
+            ("stoat", id) => Some((Platform::Stoat, id)),
+            _ => None,
+        }
+    }
+
+    pub fn mention(&self, actor: &str) -> ::std::string::String {
+        match Self::parse_actor(actor) {
+            Some((p, id)) if p == *self => format!("<@{}>", id),
+            Some((p, id)) => format!("{} ({:?})", id, p),
+            None => actor.into(),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Identity {
@@ -10,22 +36,275 @@ pub struct Identity {
     pub stoat: ::std::option::Option<::std::string::String>,
 }
 
+impl Identity {
+    pub fn resolve(target: &str, origin: &Platform) -> Self {
+        let clean = target.replace("<@", "").replace(">", "").replace("!", "");
+        let is_discord = clean.chars().all(|c| c.is_ascii_digit()) && clean.len() >= 17;
+        let is_stoat = clean.len() == 26 && clean.chars().all(|c| c.is_ascii_alphanumeric());
+        Self {
+            discord: if is_discord || *origin == Platform::Discord { Some(clean.clone()) } else { None },
+            stoat: if is_stoat || *origin == Platform::Stoat { Some(clean.clone()) } else { None },
+            raw: clean,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Proposal {
+    #[serde(default)]
+    pub tenant: ::std::string::String,
+    #[serde(default)]
+    pub case: u64,
     pub target: Identity,
+    #[serde(default)]
+    pub batch: ::std::vec::Vec<Identity>,
     pub action: ::std::string::String,
     pub reason: ::std::string::String,
+    #[serde(default)]
+    pub evidence: ::std::string::String,
+    #[serde(default)]
+    pub duration: ::std::option::Option<u64>,
+    #[serde(default)]
+    pub delete_days: u8,
+    #[serde(default)]
+    pub emergency: bool,
+    #[serde(default)]
+    pub role: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub priors: ::std::vec::Vec<Infraction>,
+    #[serde(default)]
+    pub appeal: ::std::option::Option<u64>,
     pub author: ::std::string::String,
     pub origin: Platform,
     pub channel: ::std::string::String,
     pub approvers: ::std::vec::Vec<::std::string::String>,
+    #[serde(default)]
+    pub rejects: ::std::vec::Vec<::std::string::String>,
+    #[serde(default)]
+    pub abstains: ::std::vec::Vec<::std::string::String>,
     pub timestamp: u64,
+    #[serde(default)]
+    pub posts: ::std::vec::Vec<(Platform, ::std::string::String)>,
+    #[serde(default)]
+    pub discussion: ::std::vec::Vec<Comment>,
+    #[serde(default)]
+    pub status: ::std::option::Option<::std::string::String>,
+    #[serde(default)]
+    pub closed_by: ::std::option::Option<::std::string::String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub origin: Platform,
+    pub author: ::std::string::String,
+    pub text: ::std::string::String,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub posts: ::std::vec::Vec<::std::string::String>,
+}
+
+impl Proposal {
+    pub fn subjects(&self) -> ::std::vec::Vec<&Identity> {
+        if self.batch.is_empty() { vec![&self.target] } else { self.batch.iter().collect() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Infraction {
+    pub action: ::std::string::String,
+    pub reason: ::std::string::String,
+    pub author: ::std::string::String,
+    pub approvers: ::std::vec::Vec<::std::string::String>,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub duration: ::std::option::Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities(pub ::std::vec::Vec<::std::string::String>);
+
+impl Capabilities {
+    pub fn all() -> Self {
+        Self(vec!["*".into()])
+    }
+
+    pub fn allows(&self, cap: &str) -> bool {
+        self.0.iter().any(|c| c == cap || c.strip_suffix('*').is_some_and(|prefix| cap.starts_with(prefix)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Draft {
+    pub tenant: ::std::string::String,
+    pub action: ::std::string::String,
+    pub target: ::std::string::String,
+    pub batch: ::std::vec::Vec<::std::string::String>,
+    pub role: ::std::option::Option<::std::string::String>,
+    pub author: ::std::string::String,
+    pub origin: Platform,
+    pub channel: ::std::string::String,
+    pub reason: ::std::string::String,
+    pub evidence: ::std::string::String,
+    pub duration: ::std::option::Option<u64>,
+    pub delete_days: u8,
+    pub emergency: bool,
+    pub appeal: ::std::option::Option<u64>,
+    pub caps: Capabilities,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote { Approve, Reject, Abstain }
+
 #[derive(Debug)]
 pub enum Event {
-    Propose { action: ::std::string::String, target: ::std::string::String, author: ::std::string::String, origin: Platform, channel: ::std::string::String, reason: ::std::string::String },
-    Approve { target: ::std::string::String, approver: ::std::string::String },
-    Cancel { target: ::std::string::String, author: ::std::string::String },
+    Propose(Draft),
+    Approve { tenant: ::std::string::String, target: ::std::string::String, approver: ::std::string::String, origin: Platform, caps: Capabilities },
+    Cancel { tenant: ::std::string::String, target: ::std::string::String, author: ::std::string::String, origin: Platform, caps: Capabilities },
+    Reject { tenant: ::std::string::String, target: ::std::string::String, voter: ::std::string::String, origin: Platform, caps: Capabilities },
+    Abstain { tenant: ::std::string::String, target: ::std::string::String, voter: ::std::string::String, origin: Platform, caps: Capabilities },
+    Withdraw { tenant: ::std::string::String, target: ::std::string::String, voter: ::std::string::String, origin: Platform, vote: ::std::option::Option<Vote> },
+    Toggle { tenant: ::std::string::String, target: ::std::string::String, voter: ::std::string::String, origin: Platform, caps: Capabilities },
+    Amend { tenant: ::std::string::String, target: ::std::string::String, author: ::std::string::String, origin: Platform, action: ::std::option::Option<::std::string::String>, reason: ::std::option::Option<::std::string::String>, duration: ::std::option::Option<u64>, caps: Capabilities },
+    Link { tenant: ::std::string::String, discord: ::std::string::String, stoat: ::std::string::String, author: ::std::string::String, origin: Platform, caps: Capabilities },
+    Comment { tenant: ::std::string::String, origin: Platform, author: ::std::string::String, text: ::std::string::String, refs: ::std::vec::Vec<::std::string::String>, post: ::std::string::String },
+    History { tenant: ::std::string::String, target: ::std::string::String, origin: Platform, trail: bool },
+    Pending { tenant: ::std::string::String, reply: ::tokio::sync::oneshot::Sender<::std::vec::Vec<Proposal>> },
+    Case { tenant: ::std::string::String, reference: ::std::string::String, origin: Platform, trail: bool },
     Sweep,
-}
\ No newline at end of file
+}
+
+pub const SYSTEM_AUTHOR: &str = "Urchin";
+pub const SERVER_ACTIONS: [&str; 2] = ["lockdown", "unlock"];
+pub const ROLE_ACTIONS: [&str; 2] = ["role-add", "role-remove"];
+pub const INFRACTION_ACTIONS: [&str; 3] = ["warn", "kick", "ban"];
+pub const REASON_MAX: u16 = 1000;
+
+pub type Outcome = ::std::result::Result<::std::string::String, ::std::string::String>;
+pub type Report = ::std::vec::Vec<(::std::string::String, ::std::result::Result<(), ::std::string::String>)>;
+pub type Envelope = (Event, ::std::option::Option<::tokio::sync::oneshot::Sender<Outcome>>);
+
+pub async fn request(tx: &::tokio::sync::mpsc::Sender<Envelope>, event: Event) -> Outcome {
+    let (reply, rx) = ::tokio::sync::oneshot::channel();
+    tx.send((event, Some(reply))).await.map_err(|_| "Engine unavailable.".to_string())?;
+    rx.await.unwrap_or_else(|_| Err("Engine dropped the request.".into()))
+}
+
+pub async fn pending(tx: &::tokio::sync::mpsc::Sender<Envelope>, tenant: &str) -> ::std::result::Result<::std::vec::Vec<Proposal>, ::std::string::String> {
+    let (reply, rx) = ::tokio::sync::oneshot::channel();
+    request(tx, Event::Pending { tenant: tenant.into(), reply }).await?;
+    rx.await.map_err(|_| "Engine dropped the request.".to_string())
+}
+
+pub fn case_number(reference: &str) -> ::std::option::Option<u64> {
+    let r = reference.trim().trim_start_matches('#');
+    if r.len() < 17 { r.parse().ok() } else { None }
+}
+
+pub fn appeal_args<'a>(args: &[&'a str]) -> ::std::option::Option<(::std::option::Option<&'a str>, u64, ::std::string::String)> {
+    let (name, args) = match args.first().and_then(|a| case_number(a)) {
+        Some(_) => (None, args),
+        None => (Some(*args.first()?), &args[1..]),
+    };
+    let case = case_number(args.first()?)?;
+    let reason = args[1..].join(" ");
+    if reason.is_empty() { None } else { Some((name, case, reason)) }
+}
+
+pub fn parse_duration(s: &str) -> ::std::option::Option<u64> {
+    let s = s.trim();
+    let (num, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let mult = match unit { "" | "s" => 1, "m" => 60, "h" => 3600, "d" => 86400, "w" => 604800, _ => return None };
+    num.parse::<u64>().ok()?.checked_mul(mult)
+}
+
+pub fn clip(text: &str, limit: usize) -> ::std::string::String {
+    if text.chars().count() <= limit { return text.into(); }
+    text.chars().take(limit.saturating_sub(1)).chain(::std::iter::once('…')).collect()
+}
+
+pub fn format_duration(secs: u64) -> ::std::string::String {
+    [(604800, "w"), (86400, "d"), (3600, "h"), (60, "m")].iter()
+        .find(|(m, _)| secs >= *m && secs.is_multiple_of(*m))
+        .map(|(m, u)| format!("{}{}", secs / m, u))
+        .unwrap_or(format!("{}s", secs))
+}
+
+pub fn raid_proposal(tenant: ::std::string::String, suspects: ::std::vec::Vec<::std::string::String>, reason: ::std::string::String, origin: Platform, channel: ::std::string::String) -> Event {
+    Event::Propose(Draft { tenant, action: "ban".into(), batch: suspects, author: SYSTEM_AUTHOR.into(), origin, channel, reason, caps: Capabilities::all(), ..Default::default() })
+}
+
+pub fn chunk_lines(head: &str, lines: &[::std::string::String], limit: usize) -> ::std::vec::Vec<::std::string::String> {
+    let mut chunks = vec![head.to_string()];
+    for line in lines {
+        let last = chunks.last_mut().expect("chunks starts non-empty");
+        if last.len() + line.len() + 1 > limit { chunks.push(line.clone()); } else { last.push('\n'); last.push_str(line); }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90"), Some(90));
+        assert_eq!(parse_duration("30m"), Some(1800));
+        assert_eq!(parse_duration(" 12h "), Some(43200));
+        assert_eq!(parse_duration("7d"), Some(604800));
+        assert_eq!(parse_duration("2w"), Some(1209600));
+    }
+
+    #[test]
+    fn rejects_bad_durations() {
+        assert_eq!(parse_duration("7y"), None);
+        assert_eq!(parse_duration("d"), None);
+        assert_eq!(parse_duration("-1d"), None);
+        assert_eq!(parse_duration(&format!("{}w", u64::MAX / 2)), None);
+    }
+
+    #[test]
+    fn case_numbers_are_short_numerics() {
+        assert_eq!(case_number("412"), Some(412));
+        assert_eq!(case_number(" #7 "), Some(7));
+        assert_eq!(case_number("123456789012345678"), None);
+        assert_eq!(case_number("01HZX"), None);
+        assert_eq!(case_number(""), None);
+    }
+
+    #[test]
+    fn appeal_args_with_and_without_tenant() {
+        assert_eq!(appeal_args(&["412", "please", "reconsider"]), Some((None, 412, "please reconsider".into())));
+        assert_eq!(appeal_args(&["alpha", "#7", "sorry"]), Some((Some("alpha"), 7, "sorry".into())));
+    }
+
+    #[test]
+    fn appeal_args_need_case_and_statement() {
+        assert_eq!(appeal_args(&[]), None);
+        assert_eq!(appeal_args(&["412"]), None);
+        assert_eq!(appeal_args(&["alpha", "sorry"]), None);
+        assert_eq!(appeal_args(&["alpha", "7"]), None);
+    }
+
+    #[test]
+    fn actors_are_platform_qualified() {
+        let id = Platform::Discord.actor("123456789012345678");
+        assert_eq!(id, "discord:123456789012345678");
+        assert_eq!(Platform::parse_actor(&id), Some((Platform::Discord, "123456789012345678")));
+        assert_eq!(Platform::parse_actor(SYSTEM_AUTHOR), None);
+        assert_eq!(Platform::Discord.mention(&id), "<@123456789012345678>");
+        assert_eq!(Platform::Stoat.mention(&id), "123456789012345678 (Discord)");
+        assert_eq!(Platform::Stoat.mention(SYSTEM_AUTHOR), SYSTEM_AUTHOR);
+    }
+
+    #[test]
+    fn clips_to_limit() {
+        assert_eq!(clip("short", 10), "short");
+        assert_eq!(clip("abcdef", 4), "abc…");
+        assert_eq!(clip("ééééé", 3).chars().count(), 3);
+    }
+}
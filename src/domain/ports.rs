@@ -1,4 +1,4 @@
-use super::models::Proposal;
+use super::models::{Comment, Identity, Infraction, Platform, Proposal, Report};
 use ::anyhow::Result;
 
 #[::async_trait::async_trait]
@@ -7,11 +7,34 @@ pub trait StateStore: Send + Sync {
     async fn get(&self, target: &str) -> Result<::std::option::Option<Proposal>>;
     async fn remove(&self, target: &str) -> Result<()>;
     async fn list(&self) -> Result<::std::vec::Vec<Proposal>>;
+    async fn next_case(&self) -> Result<u64>;
+    async fn archive(&self, p: &Proposal) -> Result<()>;
+    async fn case(&self, case: u64) -> Result<::std::option::Option<Proposal>>;
+    async fn audit(&self, key: &str, entry: &str) -> Result<()>;
+    async fn trail(&self, key: &str) -> Result<::std::vec::Vec<::std::string::String>>;
+    async fn record(&self, user: &str, infraction: &Infraction) -> Result<()>;
+    async fn infractions(&self, user: &str) -> Result<::std::vec::Vec<Infraction>>;
+    async fn set_link(&self, discord: &str, stoat: &str) -> Result<()>;
+    async fn linked(&self, platform: &Platform, id: &str) -> Result<::std::option::Option<::std::string::String>>;
+    async fn snapshot(&self, key: &str) -> Result<::std::option::Option<::std::string::String>>;
+    async fn set_snapshot(&self, key: &str, snapshot: ::std::option::Option<&str>) -> Result<()>;
+    async fn index(&self, post: &str, case: ::std::option::Option<(u64, &str)>) -> Result<()>;
+    async fn indexed(&self, post: &str) -> Result<::std::option::Option<(u64, ::std::string::String)>>;
+    async fn schedule(&self, subject: &str, expiry: ::std::option::Option<(u64, &Proposal)>) -> Result<()>;
+    async fn expired(&self, now: u64) -> Result<::std::vec::Vec<Proposal>>;
 }
 
 #[::async_trait::async_trait]
 pub trait Driver: Send + Sync {
-    async fn notify(&self, p: &Proposal) -> Result<()>;
-    async fn execute(&self, p: &Proposal, approver: &str) -> Result<()>;
+    fn platform(&self) -> Platform;
+    async fn notify(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>>;
+    async fn refresh(&self, p: &Proposal, post: &str) -> Result<()>;
+    async fn execute(&self, p: &Proposal, approver: &str) -> Result<Report>;
     async fn discard(&self, p: &Proposal, reason: &str) -> Result<()>;
+    async fn revert(&self, p: &Proposal) -> Result<()>;
+    async fn alert(&self, p: &Proposal, text: &str) -> Result<()>;
+    async fn relay(&self, p: &Proposal, comment: &Comment) -> Result<::std::option::Option<::std::string::String>>;
+    async fn message_user(&self, user: &Identity, text: &str) -> Result<()>;
+    async fn lockdown(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>>;
+    async fn restore(&self, p: &Proposal, snapshot: &str) -> Result<()>;
 }
\ No newline at end of file
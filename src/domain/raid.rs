@@ -0,0 +1,84 @@
+use crate::config::RaidConfig;
+use ::std::collections::{HashSet, VecDeque};
+
+pub struct RaidWatch {
+    cfg: RaidConfig,
+    joins: VecDeque<(u64, ::std::string::String)>,
+    posts: VecDeque<(u64, ::std::string::String, ::std::string::String)>,
+}
+
+impl RaidWatch {
+    pub fn new(cfg: RaidConfig) -> Self {
+        Self { cfg, joins: VecDeque::new(), posts: VecDeque::new() }
+    }
+
+    pub fn join(&mut self, user: &str, now: u64) -> ::std::option::Option<::std::vec::Vec<::std::string::String>> {
+        self.joins.push_back((now, user.into()));
+        while self.joins.front().is_some_and(|(t, _)| now > t + self.cfg.join_window_seconds) { self.joins.pop_front(); }
+        if self.joins.len() < self.cfg.join_threshold { return None; }
+        Some(Self::distinct(self.joins.drain(..).map(|(_, u)| u)))
+    }
+
+    pub fn message(&mut self, user: &str, content: &str, now: u64) -> ::std::option::Option<::std::vec::Vec<::std::string::String>> {
+        let content = content.trim().to_lowercase();
+        if content.is_empty() { return None; }
+        self.posts.push_back((now, user.into(), content.clone()));
+        while self.posts.front().is_some_and(|(t, _, _)| now > t + self.cfg.flood_window_seconds) { self.posts.pop_front(); }
+        if self.posts.iter().filter(|(_, _, c)| *c == content).count() < self.cfg.flood_threshold { return None; }
+        let flooders = Self::distinct(self.posts.iter().filter(|(_, _, c)| *c == content).map(|(_, u, _)| u.clone()));
+        self.posts.retain(|(_, _, c)| *c != content);
+        Some(flooders)
+    }
+
+    fn distinct(users: impl Iterator<Item = ::std::string::String>) -> ::std::vec::Vec<::std::string::String> {
+        let mut seen = HashSet::new();
+        users.filter(|u| seen.insert(u.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch() -> RaidWatch {
+        RaidWatch::new(RaidConfig { join_threshold: 3, join_window_seconds: 10, flood_threshold: 3, flood_window_seconds: 10 })
+    }
+
+    #[test]
+    fn join_spike_trips_once() {
+        let mut w = watch();
+        assert_eq!(w.join("a", 0), None);
+        assert_eq!(w.join("b", 1), None);
+        assert_eq!(w.join("c", 2), Some(vec!["a".into(), "b".into(), "c".into()]));
+        assert_eq!(w.join("d", 3), None);
+    }
+
+    #[test]
+    fn joins_outside_window_expire() {
+        let mut w = watch();
+        w.join("a", 0);
+        w.join("b", 1);
+        assert_eq!(w.join("c", 20), None);
+        assert_eq!(w.join("c", 21), None);
+        assert_eq!(w.join("d", 22), Some(vec!["c".into(), "d".into()]));
+    }
+
+    #[test]
+    fn flood_matches_normalised_content() {
+        let mut w = watch();
+        assert_eq!(w.message("a", "FREE NITRO", 0), None);
+        assert_eq!(w.message("b", "hello", 1), None);
+        assert_eq!(w.message("a", " free nitro ", 2), None);
+        assert_eq!(w.message("c", "free nitro", 3), Some(vec!["a".into(), "c".into()]));
+        assert_eq!(w.message("d", "free nitro", 4), None);
+    }
+
+    #[test]
+    fn flood_ignores_empty_and_stale_posts() {
+        let mut w = watch();
+        for t in 0..5 { assert_eq!(w.message("a", "   ", t), None); }
+        w.message("a", "spam", 0);
+        w.message("b", "spam", 1);
+        assert_eq!(w.message("c", "spam", 30), None);
+    }
+}
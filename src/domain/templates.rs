@@ -0,0 +1,65 @@
+use super::models::{format_duration, Infraction, Platform, Proposal};
+use ::std::{collections::HashMap, fs, path::Path};
+
+const FALLBACK: &str = include_str!("../../locales/en.toml");
+
+#[derive(Debug, Clone, Default)]
+pub struct Templates(HashMap<::std::string::String, HashMap<::std::string::String, ::std::string::String>>);
+
+impl Templates {
+    pub fn load(dir: &Path) -> ::anyhow::Result<Self> {
+        let mut locales = HashMap::from([("en".to_string(), flatten("", &::toml::from_str(FALLBACK)?))]);
+        for path in fs::read_dir(dir).into_iter().flatten().flatten().map(|e| e.path()) {
+            let (Some(code), Some("toml")) = (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|e| e.to_str())) else { continue; };
+            locales.entry(code.to_string()).or_default().extend(flatten("", &::toml::from_str(&fs::read_to_string(&path)?)?));
+        }
+        Ok(Self(locales))
+    }
+
+    pub fn render(&self, locale: &str, key: &str, vars: &[(&str, &str)]) -> ::std::string::String {
+        let template = [locale, "en"].iter().find_map(|l| self.0.get(*l)?.get(key)).map_or(key, |t| t.as_str());
+        let mut out = ::std::string::String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start + 1..];
+            match tail.find('}').and_then(|end| vars.iter().find(|(k, _)| *k == &tail[..end]).map(|(_, v)| (end, v))) {
+                Some((end, v)) => { out.push_str(v); rest = &tail[end + 1..]; }
+                None => { out.push('{'); rest = tail; }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    pub fn proposal(&self, locale: &str, platform: &Platform, key: &str, p: &Proposal, extra: &[(&str, &str)]) -> ::std::string::String {
+        let (case, author, approvers) = (p.case.to_string(), platform.mention(&p.author), p.approvers.iter().map(|a| platform.mention(a)).collect::<::std::vec::Vec<_>>().join(", "));
+        let mut vars = vec![("case", case.as_str()), ("action", p.action.as_str()), ("target", p.target.raw.as_str()), ("reason", p.reason.as_str()), ("author", author.as_str()), ("approvers", approvers.as_str())];
+        vars.extend_from_slice(extra);
+        self.render(locale, key, &vars)
+    }
+
+    pub fn pending(&self, locale: &str, platform: &Platform, p: &Proposal, required: usize, now: u64) -> ::std::string::String {
+        let age = format_duration(now.saturating_sub(p.timestamp).max(60) / 60 * 60);
+        let (approvals, rejections, required) = (p.approvers.len().to_string(), p.rejects.len().to_string(), required.to_string());
+        self.proposal(locale, platform, "pending.line", p, &[("age", &age), ("approvals", &approvals), ("rejections", &rejections), ("required", &required)])
+    }
+
+    pub fn infraction(&self, locale: &str, platform: &Platform, i: &Infraction, now: u64) -> ::std::string::String {
+        let approvers = if i.approvers.is_empty() { self.render(locale, "infraction.emergency", &[]) } else { i.approvers.iter().map(|a| platform.mention(a)).collect::<::std::vec::Vec<_>>().join(", ") };
+        let action = match i.duration { Some(d) => format!("{} ({})", i.action, format_duration(d)), None => i.action.clone() };
+        let ago = format_duration(now.saturating_sub(i.timestamp).max(60) / 60 * 60);
+        self.render(locale, "infraction.line", &[("action", &action), ("ago", &ago), ("reason", &i.reason), ("author", &platform.mention(&i.author)), ("approvers", &approvers)])
+    }
+}
+
+fn flatten(prefix: &str, table: &::toml::Table) -> HashMap<::std::string::String, ::std::string::String> {
+    table.iter().flat_map(|(k, v)| {
+        let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+        match v {
+            ::toml::Value::Table(t) => flatten(&key, t).into_iter().collect::<::std::vec::Vec<_>>(),
+            ::toml::Value::String(s) => vec![(key, s.clone())],
+            _ => vec![],
+        }
+    }).collect()
+}
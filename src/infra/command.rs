@@ -0,0 +1,156 @@
+use ::std::collections::HashMap;
+
+pub const COMMANDS: [(&str, usize); 20] = [
+    ("ban", 2), ("kick", 2), ("warn", 2), ("emergency", 3), ("bulk", 2), ("role-add", 3), ("role-remove", 3), ("lockdown", 1), ("unlock", 1),
+    ("approve", 1), ("reject", 1), ("cancel", 1), ("pending", 0), ("status", 1), ("amend", 1), ("case", 1), ("infractions", 1), ("link", 2), ("appeal", 2), ("help", 0),
+];
+pub const FLAGS: [&str; 4] = ["duration", "delete", "joined", "action"];
+
+#[derive(Debug, Default)]
+pub struct Command {
+    pub name: ::std::string::String,
+    pub args: ::std::vec::Vec<::std::string::String>,
+    pub flags: HashMap<::std::string::String, ::std::string::String>,
+}
+
+#[derive(Debug)]
+pub enum SyntaxError {
+    Unterminated,
+    MissingValue(::std::string::String),
+    UnknownFlag(::std::string::String),
+    Arity(::std::string::String),
+}
+
+impl Command {
+    pub fn args(&self) -> ::std::vec::Vec<&str> {
+        self.args.iter().map(::std::string::String::as_str).collect()
+    }
+
+    pub fn flags(&self) -> HashMap<&str, &str> {
+        self.flags.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+    }
+}
+
+pub fn parse(content: &str, prefix: &str) -> ::std::option::Option<::std::result::Result<Command, SyntaxError>> {
+    let body = content.trim().strip_prefix(prefix)?;
+    let name = body.split_whitespace().next()?.to_lowercase();
+    let (_, min) = COMMANDS.iter().find(|(c, _)| *c == name)?;
+    Some(tokens(&body[body.find(char::is_whitespace).unwrap_or(body.len())..]).and_then(|tokens| {
+        let mut cmd = Command { name: name.clone(), ..Default::default() };
+        let mut tokens = tokens.into_iter();
+        while let Some((token, quoted)) = tokens.next() {
+            let Some(flag) = token.strip_prefix("--").filter(|f| !quoted && !f.is_empty()) else {
+                cmd.args.push(if quoted { token } else { mention(&token) });
+                continue;
+            };
+            let (flag, value) = match flag.split_once('=') {
+                Some((f, v)) => (f.to_string(), v.to_string()),
+                None => match tokens.next() {
+                    Some((v, q)) if q || !v.starts_with("--") => (flag.to_string(), v),
+                    _ => return Err(SyntaxError::MissingValue(flag.into())),
+                },
+            };
+            if !FLAGS.contains(&flag.as_str()) { return Err(SyntaxError::UnknownFlag(flag)); }
+            cmd.flags.insert(flag, value);
+        }
+        if cmd.args.len() < *min { return Err(SyntaxError::Arity(name)); }
+        Ok(cmd)
+    }))
+}
+
+fn tokens(s: &str) -> ::std::result::Result<::std::vec::Vec<(::std::string::String, bool)>, SyntaxError> {
+    let (mut out, mut cur, mut quote, mut quoted, mut chars) = (::std::vec::Vec::new(), ::std::string::String::new(), None, false, s.chars());
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), '\\') => cur.extend(chars.next()),
+            (Some(_), c) => cur.push(c),
+            (None, '"' | '\'') if cur.is_empty() && !quoted => { quote = Some(c); quoted = true; }
+            (None, c) if c.is_whitespace() => {
+                if !cur.is_empty() || quoted { out.push((::std::mem::take(&mut cur), quoted)); }
+                quoted = false;
+            }
+            (None, c) => cur.push(c),
+        }
+    }
+    if quote.is_some() { return Err(SyntaxError::Unterminated); }
+    if !cur.is_empty() || quoted { out.push((cur, quoted)); }
+    Ok(out)
+}
+
+fn mention(token: &str) -> ::std::string::String {
+    ["<@", "<%", "<#"].iter().find_map(|p| token.strip_prefix(p)?.strip_suffix('>')).unwrap_or(token).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok(content: &str) -> Command {
+        parse(content, "/").expect("is a command").expect("parses")
+    }
+
+    fn err(content: &str) -> SyntaxError {
+        parse(content, "/").expect("is a command").expect_err("fails")
+    }
+
+    #[test]
+    fn ignores_plain_text_and_unknown_commands() {
+        assert!(parse("hello there", "/").is_none());
+        assert!(parse("/dance now", "/").is_none());
+        assert!(parse("/", "/").is_none());
+    }
+
+    #[test]
+    fn splits_arguments_and_strips_mentions() {
+        let cmd = ok("/BAN <@01HZZZZZZZZZZZZZZZZZZZZZZZ> spam links");
+        assert_eq!(cmd.name, "ban");
+        assert_eq!(cmd.args(), ["01HZZZZZZZZZZZZZZZZZZZZZZZ", "spam", "links"]);
+        assert_eq!(ok("/role-add <@u1> <%r1> reason").args(), ["u1", "r1", "reason"]);
+    }
+
+    #[test]
+    fn quotes_group_words_and_escape() {
+        assert_eq!(ok(r#"/ban u1 "spam and phishing""#).args(), ["u1", "spam and phishing"]);
+        assert_eq!(ok("/ban u1 'it\\'s spam'").args(), ["u1", "it's spam"]);
+        assert_eq!(ok(r#"/ban u1 """#).args(), ["u1", ""]);
+        assert_eq!(ok(r#"/ban u1 "<@u2>""#).args(), ["u1", "<@u2>"]);
+        assert_eq!(ok(r#"/ban u1 don"t"#).args(), ["u1", r#"don"t"#]);
+    }
+
+    #[test]
+    fn reports_unterminated_quotes() {
+        assert!(matches!(err(r#"/ban u1 "spam"#), SyntaxError::Unterminated));
+    }
+
+    #[test]
+    fn reads_flags_in_both_forms() {
+        let cmd = ok("/ban u1 spam --duration 7d --delete=2");
+        assert_eq!(cmd.args(), ["u1", "spam"]);
+        assert_eq!(cmd.flags().get("duration"), Some(&"7d"));
+        assert_eq!(cmd.flags().get("delete"), Some(&"2"));
+        assert_eq!(ok(r#"/ban u1 spam --duration "--x""#).flags().get("duration"), Some(&"--x"));
+        assert_eq!(ok(r#"/ban u1 "--duration" spam"#).args(), ["u1", "--duration", "spam"]);
+    }
+
+    #[test]
+    fn reports_missing_flag_values() {
+        assert!(matches!(err("/ban u1 spam --duration"), SyntaxError::MissingValue(f) if f == "duration"));
+        assert!(matches!(err("/ban u1 spam --duration --delete 1"), SyntaxError::MissingValue(f) if f == "duration"));
+    }
+
+    #[test]
+    fn reports_unknown_flags() {
+        assert!(matches!(err("/ban u1 spam --silent yes"), SyntaxError::UnknownFlag(f) if f == "silent"));
+        assert!(matches!(err("/ban u1 spam --silent=yes"), SyntaxError::UnknownFlag(f) if f == "silent"));
+    }
+
+    #[test]
+    fn checks_arity_after_flags() {
+        assert!(matches!(err("/ban u1"), SyntaxError::Arity(n) if n == "ban"));
+        assert!(matches!(err("/ban u1 --duration 7d"), SyntaxError::Arity(n) if n == "ban"));
+        assert!(matches!(err("/role-add u1 r1"), SyntaxError::Arity(n) if n == "role-add"));
+        assert_eq!(ok("/pending").args().len(), 0);
+        assert_eq!(ok("/lockdown raid").args(), ["raid"]);
+    }
+}
@@ -1,108 +1,579 @@
-use crate::config::AppConfig;
-use crate::domain::{models::{Event as AppEvent, Platform, Proposal}, ports::Driver};
+use crate::config::{AppConfig, SpaceConfig, TenantConfig};
+use crate::domain::{models::{appeal_args, chunk_lines, clip, Comment, format_duration, parse_duration, pending, raid_proposal, request, Draft, REASON_MAX, Report, Envelope, ROLE_ACTIONS, SERVER_ACTIONS, Event as AppEvent, Identity, Platform, Proposal}, ports::Driver, raid::RaidWatch};
 use ::anyhow::Result;
 use ::serenity::all::*;
-use ::std::sync::Arc;
-use ::tokio::sync::mpsc;
+use ::std::{collections::HashMap, sync::Arc};
+use ::std::time::{SystemTime, UNIX_EPOCH};
+use ::tokio::sync::{mpsc, Mutex};
 
-struct Handler { tx: mpsc::Sender<AppEvent>, guild: GuildId, staff: RoleId }
+struct Handler { tx: mpsc::Sender<Envelope>, watch: HashMap<GuildId, Mutex<RaidWatch>>, config: Arc<AppConfig> }
+
+impl Handler {
+    fn t(&self, tenant: &str, key: &str, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.text(tenant, &Platform::Discord, key, vars)
+    }
+
+    async fn pending_page(&self, tenant: &str, page: usize) -> ::std::result::Result<(CreateEmbed, ::std::vec::Vec<CreateActionRow>), ::std::string::String> {
+        const PAGE: usize = 10;
+        let open = pending(&self.tx, tenant).await?;
+        let pages = open.len().div_ceil(PAGE).max(1);
+        let page = page.min(pages - 1);
+        let shown = &open[page * PAGE..((page + 1) * PAGE).min(open.len())];
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let locale = self.config.lang(tenant, &Platform::Discord);
+        let lines: ::std::vec::Vec<_> = shown.iter().map(|p| self.config.templates.pending(locale, &Platform::Discord, p, self.config.quorum(tenant), now)).collect();
+        let body = if lines.is_empty() { self.t(tenant, "pending.none", &[]) } else { lines.join("\n") };
+        let embed = CreateEmbed::new().description(format!("{}\n{}", self.t(tenant, "pending.header", &[("count", &open.len().to_string())]), body))
+            .footer(CreateEmbedFooter::new(self.t(tenant, "pending.page", &[("page", &(page + 1).to_string()), ("pages", &pages.to_string())])));
+        let mut rows = ::std::vec::Vec::new();
+        if !shown.is_empty() {
+            let options = shown.iter().map(|p| CreateSelectMenuOption::new(self.config.templates.proposal(locale, &Platform::Discord, "pending.option", p, &[]).chars().take(100).collect::<::std::string::String>(), p.case.to_string())).collect();
+            rows.push(CreateActionRow::SelectMenu(CreateSelectMenu::new("pick", CreateSelectMenuKind::String { options }).placeholder(self.t(tenant, "pending.pick", &[]))));
+        }
+        if pages > 1 {
+            rows.push(CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("page:{}", page.saturating_sub(1))).label("◀").style(ButtonStyle::Secondary).disabled(page == 0),
+                CreateButton::new(format!("page:{}", page + 1)).label("▶").style(ButtonStyle::Secondary).disabled(page + 1 >= pages),
+            ]));
+        }
+        Ok((embed, rows))
+    }
+
+    fn input(style: InputTextStyle, label: &str, id: &str, required: bool, max: u16) -> CreateActionRow {
+        CreateActionRow::InputText(CreateInputText::new(style, label, id).required(required).max_length(max))
+    }
+
+    fn tenant(&self, guild: ::std::option::Option<GuildId>) -> ::std::option::Option<(&TenantConfig, &SpaceConfig)> {
+        self.config.tenant_of(&Platform::Discord, &guild?.to_string())
+    }
+
+    async fn appeal(&self, ctx: &Context, msg: &Message) {
+        let usage = self.t("", "appeal.usage", &[("prefix", &self.config.command_prefix)]);
+        let words: ::std::vec::Vec<_> = msg.content.split_whitespace().collect();
+        let res = match words.split_first() {
+            Some((w, args)) if w.strip_prefix(self.config.command_prefix.as_str()) == Some("appeal") => match appeal_args(args) {
+                Some((name, case, reason)) => match self.config.appeal_tenant(&Platform::Discord, name).and_then(|t| Some((t, t.discord.as_ref()?))) {
+                    Some((t, space)) => request(&self.tx, AppEvent::Propose(Draft { tenant: t.name.clone(), action: "appeal".into(), target: msg.author.id.to_string(), author: Platform::Discord.actor(&msg.author.id.to_string()), origin: Platform::Discord, channel: space.log.clone(), reason, appeal: Some(case), ..Default::default() })).await,
+                    None => Err(usage),
+                },
+                None => Err(usage),
+            },
+            _ => Err(usage),
+        };
+        let _ = msg.channel_id.say(&ctx.http, res.unwrap_or_else(|e| format!("❌ {}", e))).await;
+    }
+
+    async fn outranks(&self, ctx: &Context, guild: GuildId, user: UserId, role: RoleId) -> bool {
+        let (Ok(roles), Ok(member)) = (guild.roles(&ctx.http).await, guild.member(&ctx.http, user).await) else { return false; };
+        let top = member.roles.iter().filter_map(|r| roles.get(r)).map(|r| r.position).max().unwrap_or(0);
+        roles.get(&role).is_some_and(|r| r.position < top)
+    }
+
+    fn form(&self, tenant: &str, fields: &HashMap<&str, &str>) -> ::std::result::Result<(::std::string::String, ::std::option::Option<u64>, u8, ::std::string::String), ::std::string::String> {
+        let field = |k: &str| fields.get(k).copied().unwrap_or("");
+        if field("reason").is_empty() { return Err(self.t(tenant, "form.reason_required", &[])); }
+        let duration = match field("duration") {
+            "" => None,
+            d => Some(parse_duration(d).ok_or_else(|| self.t(tenant, "form.bad_duration", &[("value", d)]))?),
+        };
+        let delete_days = match field("delete") {
+            "" => 0,
+            d => d.parse::<u8>().ok().filter(|n| *n <= 7).ok_or_else(|| self.t(tenant, "form.bad_delete", &[("value", d)]))?,
+        };
+        Ok((field("reason").into(), duration, delete_days, field("evidence").into()))
+    }
+
+    async fn bulk(&self, ctx: &Context, tenant: &str, guild: GuildId, cmd: &CommandInteraction) -> ::std::result::Result<(::std::string::String, ::std::string::String, ::std::vec::Vec<::std::string::String>), ::std::string::String> {
+        let (mut action, mut reason, mut batch) = (::std::string::String::new(), ::std::string::String::new(), ::std::vec::Vec::new());
+        let ids = |text: &str| text.split(|c: char| !c.is_ascii_digit()).filter(|t| t.len() >= 17).map(::std::string::String::from).collect::<::std::vec::Vec<_>>();
+        for opt in cmd.data.options() {
+            match (opt.name, opt.value) {
+                ("action", ResolvedValue::String(s)) => action = s.into(),
+                ("reason", ResolvedValue::String(s)) => reason = s.into(),
+                ("ids", ResolvedValue::String(s)) => batch.extend(ids(s)),
+                ("file", ResolvedValue::Attachment(a)) => batch.extend(ids(&::std::string::String::from_utf8_lossy(&a.download().await.map_err(|e| e.to_string())?))),
+                ("joined_minutes", ResolvedValue::Integer(mins)) => {
+                    let cutoff = mins.checked_mul(60).filter(|_| (1..=1440).contains(&mins)).and_then(|s| Timestamp::now().unix_timestamp().checked_sub(s)).ok_or_else(|| self.t(tenant, "error.joined_flag", &[]))?;
+                    let mut after = None;
+                    loop {
+                        let page = guild.members(&ctx.http, Some(1000), after).await.map_err(|e| e.to_string())?;
+                        batch.extend(page.iter().filter(|m| m.joined_at.is_some_and(|j| j.unix_timestamp() >= cutoff)).map(|m| m.user.id.to_string()));
+                        after = page.last().map(|m| m.user.id);
+                        if page.len() < 1000 { break; }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if batch.is_empty() { return Err(self.t(tenant, "error.no_targets", &[])); }
+        Ok((action, reason, batch))
+    }
+}
 
 #[::async_trait::async_trait]
 impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, _: Ready) {
-        let opts = || vec![
-            CreateCommandOption::new(CommandOptionType::User, "target", "Target User").required(true),
-            CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true)
+        let target = || CreateCommandOption::new(CommandOptionType::User, "target", "Target User").required(true);
+        let commands = vec![
+            CreateCommand::new("ban").description("Propose ban").add_option(target())
+                .add_option(CreateCommandOption::new(CommandOptionType::Integer, "delete_messages", "Days of message history to delete").min_int_value(0).max_int_value(7)),
+            CreateCommand::new("kick").description("Propose kick").add_option(target()),
+            CreateCommand::new("warn").description("Propose warning").add_option(target()),
+            CreateCommand::new("case").description("Show a case by number or target")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reference", "Case number or target").required(true)),
+            CreateCommand::new("approve").description("Approve an open proposal by case number or target")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reference", "Case number or target").required(true)),
+            CreateCommand::new("amend").description("Amend your open proposal; existing votes are reset")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reference", "Case number or target").required(true))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "action", "New action").add_string_choice("warn", "warn").add_string_choice("kick", "kick").add_string_choice("ban", "ban"))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "New reason").max_length(REASON_MAX))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "duration", "New duration (e.g. 30m, 12h, 7d)")),
+            CreateCommand::new("infractions").description("Show a user's infraction record").add_option(target()),
+            CreateCommand::new("history").description("Show a user's infractions and audit trail").add_option(target()),
+            CreateCommand::new("pending").description("List open proposals"),
+            CreateCommand::new("proposal").description("Show a proposal's details and history")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "case", "Case number or target").required(true)),
+            CreateCommand::new("link").description("Link a Discord user to their Stoat account").add_option(target())
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "stoat_id", "Stoat user ID").required(true)),
+            CreateCommand::new("bulk").description("Propose an action against many users")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "action", "Action").required(true).add_string_choice("ban", "ban").add_string_choice("kick", "kick"))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "ids", "User IDs or mentions"))
+                .add_option(CreateCommandOption::new(CommandOptionType::Attachment, "file", "Text file of user IDs"))
+                .add_option(CreateCommandOption::new(CommandOptionType::Integer, "joined_minutes", "Everyone who joined in the last N minutes").min_int_value(1).max_int_value(1440)),
+            CreateCommand::new("lockdown").description("Propose restricting posting server-wide")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX)),
+            CreateCommand::new("unlock").description("Propose lifting the lockdown")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX)),
+            CreateCommand::new("role-add").description("Propose granting a role")
+                .add_option(target())
+                .add_option(CreateCommandOption::new(CommandOptionType::Role, "role", "Role").required(true))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX)),
+            CreateCommand::new("role-remove").description("Propose revoking a role")
+                .add_option(target())
+                .add_option(CreateCommandOption::new(CommandOptionType::Role, "role", "Role").required(true))
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX)),
+            CreateCommand::new("emergency").description("Execute immediately, subject to retroactive review")
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "action", "Action").required(true).add_string_choice("ban", "ban").add_string_choice("kick", "kick"))
+                .add_option(target())
+                .add_option(CreateCommandOption::new(CommandOptionType::String, "reason", "Action Reason").required(true).max_length(REASON_MAX))
         ];
-        let _ = self.guild.set_commands(&ctx, vec![
-            CreateCommand::new("ban").description("Propose ban").set_options(opts()),
-            CreateCommand::new("kick").description("Propose kick").set_options(opts())
-        ]).await;
+        for guild in self.config.tenants.iter().filter_map(|t| t.discord.as_ref()?.id.parse::<u64>().ok()) {
+            if let Err(e) = GuildId::new(guild).set_commands(&ctx, commands.clone()).await { ::tracing::error!("Discord commands for {}: {}", guild, e); }
+        }
+    }
+
+    async fn guild_member_addition(&self, _: Context, member: Member) {
+        let (Some(watch), Some((tenant, space))) = (self.watch.get(&member.guild_id), self.tenant(Some(member.guild_id))) else { return; };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let suspects = watch.lock().await.join(&member.user.id.to_string(), now);
+        if let Some(suspects) = suspects {
+            let reason = self.t(&tenant.name, "raid.joins", &[("count", &suspects.len().to_string()), ("seconds", &self.config.raid.as_ref().map_or(0, |r| r.join_window_seconds).to_string())]);
+            let _ = self.tx.send((raid_proposal(tenant.name.clone(), suspects, reason, Platform::Discord, space.log.clone()), None)).await;
+        }
+    }
+
+    async fn message(&self, ctx: Context, msg: Message) {
+        if msg.author.bot { return; }
+        if msg.guild_id.is_none() { return self.appeal(&ctx, &msg).await; }
+        let Some((tenant, space)) = self.tenant(msg.guild_id) else { return; };
+        if self.config.discussion.mirror && !msg.content.is_empty() {
+            let roles: ::std::vec::Vec<_> = msg.member.as_ref().map(|m| m.roles.iter().map(|r| r.to_string()).collect()).unwrap_or_default();
+            if !self.config.capabilities(&Platform::Discord, &roles, &space.roles).is_empty() {
+                let ev = AppEvent::Comment { tenant: tenant.name.clone(), origin: Platform::Discord, author: Platform::Discord.actor(&msg.author.id.to_string()), text: msg.content.clone(), refs: vec![msg.channel_id.to_string()], post: msg.id.to_string() };
+                let _ = self.tx.send((ev, None)).await;
+            }
+        }
+        let Some(watch) = msg.guild_id.and_then(|g| self.watch.get(&g)) else { return; };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let suspects = watch.lock().await.message(&msg.author.id.to_string(), &msg.content, now);
+        if let Some(suspects) = suspects {
+            let reason = self.t(&tenant.name, "raid.flood", &[("count", &suspects.len().to_string())]);
+            let _ = self.tx.send((raid_proposal(tenant.name.clone(), suspects, reason, Platform::Discord, space.log.clone()), None)).await;
+        }
     }
 
     async fn interaction_create(&self, ctx: Context, int: Interaction) {
-        let (has_role, author) = match &int {
-            Interaction::Command(c) => (c.user.has_role(&ctx, self.guild, self.staff).await.unwrap_or(false), c.user.name.clone()),
-            Interaction::Component(c) => (c.user.has_role(&ctx, self.guild, self.staff).await.unwrap_or(false), c.user.name.clone()),
+        let (guild, roles, author) = match &int {
+            Interaction::Command(c) => (c.guild_id, c.member.as_ref().map(|m| m.roles.clone()), Platform::Discord.actor(&c.user.id.to_string())),
+            Interaction::Component(c) => (c.guild_id, c.member.as_ref().map(|m| m.roles.clone()), Platform::Discord.actor(&c.user.id.to_string())),
+            Interaction::Modal(c) => (c.guild_id, c.member.as_ref().map(|m| m.roles.clone()), Platform::Discord.actor(&c.user.id.to_string())),
             _ => return,
         };
-        if !has_role { return; }
+        let (Some(guild), Some((tenant, space))) = (guild, self.tenant(guild)) else { return; };
+        let roles: ::std::vec::Vec<_> = roles.unwrap_or_default().iter().map(|r| r.to_string()).collect();
+        let caps = self.config.capabilities(&Platform::Discord, &roles, &space.roles);
+        if caps.is_empty() { return; }
+        let tenant = tenant.name.clone();
 
         match int {
-            Interaction::Command(cmd) => {
-                let (mut target, mut reason) = (::std::string::String::new(), ::std::string::String::new());
-                for opt in &cmd.data.options() {
+            Interaction::Command(cmd) if cmd.data.name == "bulk" => {
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = match self.bulk(&ctx, &tenant, guild, &cmd).await {
+                    Ok((action, reason, batch)) => request(&self.tx, AppEvent::Propose(Draft { tenant, action, batch, author, origin: Platform::Discord, channel: cmd.channel_id.to_string(), reason, caps, ..Default::default() })).await,
+                    Err(e) => Err(e),
+                };
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if ["case", "proposal", "approve"].contains(&cmd.data.name.as_str()) => {
+                let reference = cmd.data.options().iter().find_map(|o| match o.value { ResolvedValue::String(s) => Some(s.to_string()), _ => None }).unwrap_or_default();
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = match cmd.data.name.as_str() {
+                    "approve" => request(&self.tx, AppEvent::Approve { tenant, target: reference, approver: author, origin: Platform::Discord, caps }).await,
+                    name => request(&self.tx, AppEvent::Case { tenant, reference, origin: Platform::Discord, trail: name == "proposal" }).await,
+                };
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(clip(&res.unwrap_or_else(|e| format!("❌ {}", e)), 2000)).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if cmd.data.name == "pending" => {
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let reply = match self.pending_page(&tenant, 0).await {
+                    Ok((embed, rows)) => CreateInteractionResponseFollowup::new().embed(embed).components(rows),
+                    Err(e) => CreateInteractionResponseFollowup::new().content(format!("❌ {}", e)),
+                };
+                let _ = cmd.create_followup(&ctx, reply.ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if cmd.data.name == "amend" => {
+                let (mut target, mut action, mut reason, mut duration) = (::std::string::String::new(), None, None, Ok(None));
+                for opt in cmd.data.options() {
+                    match (opt.name, opt.value) {
+                        ("reference", ResolvedValue::String(s)) => target = s.into(),
+                        ("action", ResolvedValue::String(s)) => action = Some(s.to_string()),
+                        ("reason", ResolvedValue::String(s)) => reason = Some(s.to_string()),
+                        ("duration", ResolvedValue::String(s)) => duration = parse_duration(s).map(Some).ok_or_else(|| self.t(&tenant, "form.bad_duration", &[("value", s)])),
+                        _ => {}
+                    }
+                }
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = match duration {
+                    Ok(duration) => request(&self.tx, AppEvent::Amend { tenant, target, author, origin: Platform::Discord, action, reason, duration, caps }).await,
+                    Err(e) => Err(e),
+                };
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if cmd.data.name == "infractions" || cmd.data.name == "history" => {
+                let Some(target) = cmd.data.options().iter().find_map(|o| match o.value { ResolvedValue::User(u, _) => Some(u.id), _ => None }) else { return; };
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = request(&self.tx, AppEvent::History { tenant, target: target.to_string(), origin: Platform::Discord, trail: cmd.data.name == "history" }).await;
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(clip(&res.unwrap_or_else(|e| format!("❌ {}", e)), 2000)).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if cmd.data.name == "link" => {
+                let (mut discord, mut stoat) = (::std::string::String::new(), ::std::string::String::new());
+                for opt in cmd.data.options() {
+                    match (opt.name, opt.value) {
+                        ("target", ResolvedValue::User(u, _)) => discord = u.id.to_string(),
+                        ("stoat_id", ResolvedValue::String(s)) => stoat = s.into(),
+                        _ => {}
+                    }
+                }
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = request(&self.tx, AppEvent::Link { tenant, discord, stoat, author, origin: Platform::Discord, caps }).await;
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if SERVER_ACTIONS.contains(&cmd.data.name.as_str()) => {
+                let reason = cmd.data.options().iter().find_map(|o| match o.value { ResolvedValue::String(s) => Some(s.to_string()), _ => None }).unwrap_or_default();
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = request(&self.tx, AppEvent::Propose(Draft { tenant, action: cmd.data.name.clone(), author, origin: Platform::Discord, channel: cmd.channel_id.to_string(), reason, caps, ..Default::default() })).await;
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if ROLE_ACTIONS.contains(&cmd.data.name.as_str()) => {
+                let (mut target, mut role, mut reason) = (None, None, ::std::string::String::new());
+                for opt in cmd.data.options() {
                     match opt.value {
-                        ResolvedValue::User(u, _) => target = u.id.to_string(),
-                        ResolvedValue::String(s) => reason = s.to_string(),
+                        ResolvedValue::User(u, _) => target = Some(u.id),
+                        ResolvedValue::Role(r) => role = Some(r.id),
+                        ResolvedValue::String(s) => reason = s.into(),
                         _ => {}
                     }
                 }
-                let _ = self.tx.send(AppEvent::Propose { action: cmd.data.name.clone(), target, author, origin: Platform::Discord, channel: cmd.channel_id.to_string(), reason }).await;
-                let _ = cmd.create_response(&ctx, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("⏳ Proposed.").ephemeral(true))).await;
+                let (Some(target), Some(role)) = (target, role) else { return; };
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = match self.outranks(&ctx, guild, cmd.user.id, role).await {
+                    true => request(&self.tx, AppEvent::Propose(Draft { tenant, action: cmd.data.name.clone(), target: target.to_string(), role: Some(role.to_string()), author, origin: Platform::Discord, channel: cmd.channel_id.to_string(), reason, caps, ..Default::default() })).await,
+                    false => Err(self.t(&tenant, "error.role_rank", &[])),
+                };
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) if cmd.data.name == "emergency" => {
+                let (mut action, mut target, mut reason) = (::std::string::String::new(), ::std::string::String::new(), ::std::string::String::new());
+                for opt in cmd.data.options() {
+                    match (opt.name, opt.value) {
+                        ("action", ResolvedValue::String(s)) => action = s.into(),
+                        ("target", ResolvedValue::User(u, _)) => target = u.id.to_string(),
+                        ("reason", ResolvedValue::String(s)) => reason = s.into(),
+                        _ => {}
+                    }
+                }
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = request(&self.tx, AppEvent::Propose(Draft { tenant, action, target, author, origin: Platform::Discord, channel: cmd.channel_id.to_string(), reason, emergency: true, caps, ..Default::default() })).await;
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+            }
+            Interaction::Command(cmd) => {
+                let Some(target) = cmd.data.options().iter().find_map(|o| match o.value { ResolvedValue::User(u, _) => Some(u.id), _ => None }) else { return; };
+                let mut rows = vec![Self::input(InputTextStyle::Paragraph, &self.t(&tenant, "form.reason", &[]), "reason", true, REASON_MAX)];
+                if cmd.data.name == "ban" {
+                    rows.push(Self::input(InputTextStyle::Short, &self.t(&tenant, "form.duration", &[]), "duration", false, 16));
+                    let days = cmd.data.options().iter().find_map(|o| match o.value { ResolvedValue::Integer(d) if o.name == "delete_messages" => Some(d), _ => None });
+                    let input = CreateInputText::new(InputTextStyle::Short, self.t(&tenant, "form.delete", &[]), "delete").required(false).max_length(1);
+                    rows.push(CreateActionRow::InputText(match days { Some(d) => input.value(d.to_string()), None => input }));
+                }
+                rows.push(Self::input(InputTextStyle::Paragraph, &self.t(&tenant, "form.evidence", &[]), "evidence", false, REASON_MAX));
+                let modal = CreateModal::new(format!("propose:{}:{}", cmd.data.name, target), self.t(&tenant, "form.title", &[("action", &cmd.data.name)])).components(rows);
+                let _ = cmd.create_response(&ctx, CreateInteractionResponse::Modal(modal)).await;
+            }
+            Interaction::Modal(m) => {
+                let Some((action, target)) = m.data.custom_id.strip_prefix("propose:").and_then(|s| s.split_once(':')) else { return; };
+                let fields: HashMap<&str, &str> = m.data.components.iter().flat_map(|r| &r.components).filter_map(|c| match c {
+                    ActionRowComponent::InputText(t) => Some((t.custom_id.as_str(), t.value.as_deref().unwrap_or("").trim())),
+                    _ => None,
+                }).collect();
+                match self.form(&tenant, &fields) {
+                    Ok((reason, duration, delete_days, evidence)) => {
+                        let _ = m.defer_ephemeral(&ctx).await;
+                        let res = request(&self.tx, AppEvent::Propose(Draft { tenant, action: action.into(), target: target.into(), author, origin: Platform::Discord, channel: m.channel_id.to_string(), reason, evidence, duration, delete_days, caps, ..Default::default() })).await;
+                        let _ = m.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
+                    }
+                    Err(e) => { let _ = m.create_response(&ctx, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(format!("❌ {}", e)).ephemeral(true))).await; }
+                }
+            }
+            Interaction::Component(cmd) if cmd.data.custom_id.starts_with("page:") => {
+                let page = cmd.data.custom_id["page:".len()..].parse().unwrap_or(0);
+                let reply = match self.pending_page(&tenant, page).await {
+                    Ok((embed, rows)) => CreateInteractionResponseMessage::new().embed(embed).components(rows),
+                    Err(e) => CreateInteractionResponseMessage::new().content(format!("❌ {}", e)).embeds(vec![]).components(vec![]),
+                };
+                let _ = cmd.create_response(&ctx, CreateInteractionResponse::UpdateMessage(reply)).await;
+            }
+            Interaction::Component(cmd) if cmd.data.custom_id == "pick" => {
+                let ComponentInteractionDataKind::StringSelect { values } = &cmd.data.kind else { return; };
+                let Some(reference) = values.first().cloned() else { return; };
+                let _ = cmd.defer_ephemeral(&ctx).await;
+                let res = request(&self.tx, AppEvent::Case { tenant, reference, origin: Platform::Discord, trail: true }).await;
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(clip(&res.unwrap_or_else(|e| format!("❌ {}", e)), 2000)).ephemeral(true)).await;
             }
             Interaction::Component(cmd) => {
-                let mut msg = cmd.message.clone();
-                if let Some(target) = cmd.data.custom_id.strip_prefix("ok:") {
-                    let _ = self.tx.send(AppEvent::Approve { target: target.into(), approver: author }).await;
-                    let _ = cmd.create_response(&ctx, CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("✅ Processing...").ephemeral(true))).await;
+                let res = if let Some(target) = cmd.data.custom_id.strip_prefix("ok:") {
+                    let _ = cmd.create_response(&ctx, CreateInteractionResponse::Acknowledge).await;
+                    request(&self.tx, AppEvent::Toggle { tenant, target: target.into(), voter: author, origin: Platform::Discord, caps }).await
                 } else if let Some(target) = cmd.data.custom_id.strip_prefix("no:") {
-                    let _ = self.tx.send(AppEvent::Cancel { target: target.into(), author }).await;
-                    let _ = msg.edit(&ctx, EditMessage::new().components(vec![])).await;
                     let _ = cmd.create_response(&ctx, CreateInteractionResponse::Acknowledge).await;
-                }
+                    request(&self.tx, AppEvent::Cancel { tenant, target: target.into(), author, origin: Platform::Discord, caps }).await
+                } else { return; };
+                let _ = cmd.create_followup(&ctx, CreateInteractionResponseFollowup::new().content(res.unwrap_or_else(|e| format!("❌ {}", e))).ephemeral(true)).await;
             }
             _ => {}
         }
     }
 }
 
-pub struct Discord { http: Arc<Http>, log: ChannelId, config: Arc<AppConfig> }
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+enum Snapshot { Verification(u8), Channels(::std::vec::Vec<(u64, ::std::option::Option<(u64, u64)>)>) }
+
+pub struct Discord { http: Arc<Http>, config: Arc<AppConfig> }
 
 impl Discord {
-    pub async fn new(token: &str, guild: u64, staff: u64, log: u64, tx: mpsc::Sender<AppEvent>, config: Arc<AppConfig>) -> Result<Self> {
-        let mut client = Client::builder(token, GatewayIntents::GUILD_MESSAGES | GatewayIntents::GUILD_MEMBERS)
-            .event_handler(Handler { tx, guild: GuildId::new(guild), staff: RoleId::new(staff) }).await?;
+    pub async fn new(token: &str, tx: mpsc::Sender<Envelope>, config: Arc<AppConfig>) -> Result<Self> {
+        let mut intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::GUILD_MEMBERS | GatewayIntents::DIRECT_MESSAGES;
+        if config.raid.is_some() || config.discussion.mirror { intents |= GatewayIntents::MESSAGE_CONTENT; }
+        let watch = config.tenants.iter().filter_map(|t| t.discord.as_ref()?.id.parse::<u64>().ok())
+            .filter_map(|g| Some((GuildId::new(g), Mutex::new(RaidWatch::new(config.raid.clone()?))))).collect();
+        let mut client = Client::builder(token, intents)
+            .event_handler(Handler { tx, watch, config: Arc::clone(&config) }).await?;
         ::tokio::spawn(async move { let _ = client.start().await; });
-        Ok(Self { http: Arc::new(Http::new(token)), log: ChannelId::new(log), config })
+        Ok(Self { http: Arc::new(Http::new(token)), config })
+    }
+
+    fn space(&self, p: &Proposal) -> ::std::option::Option<&SpaceConfig> {
+        self.config.space(&p.tenant, &Platform::Discord)
+    }
+
+    fn origin(&self, p: &Proposal) -> ::std::option::Option<ChannelId> {
+        self.config.tenant(&p.tenant)?.route(&Platform::Discord, &p.origin, &p.channel)?.parse().ok().map(ChannelId::new)
+    }
+
+    fn review(&self, p: &Proposal) -> ::std::option::Option<ChannelId> {
+        self.space(p)?.review.channel(&p.action, p.emergency)?.parse().ok().map(ChannelId::new)
+    }
+
+    fn channel(&self, p: &Proposal) -> ::std::option::Option<ChannelId> {
+        self.review(p).or_else(|| self.origin(p))
+    }
+
+    fn guild(&self, p: &Proposal) -> ::std::option::Option<GuildId> {
+        self.space(p)?.id.parse().ok().map(GuildId::new)
+    }
+
+    fn text(&self, key: &str, p: &Proposal, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.templates.proposal(self.config.lang(&p.tenant, &Platform::Discord), &Platform::Discord, key, p, vars)
+    }
+
+    fn embed(&self, p: &Proposal) -> CreateEmbed {
+        let t = |key: &str, vars: &[(&str, &str)]| self.text(key, p, vars);
+        let mut embed = CreateEmbed::new().title(t("proposal.title", &[]))
+            .field(t("proposal.target", &[]), match p.batch.len() {
+                0 => p.target.discord.as_deref().map_or(p.target.raw.clone(), |d| format!("<@{}>", d)),
+                n => clip(&t("proposal.batch", &[("count", &n.to_string()), ("list", &p.batch.iter().take(30).map(|i| format!("<@{}>", i.discord.as_deref().unwrap_or(&i.raw))).collect::<::std::vec::Vec<_>>().join(" "))]), 1024),
+            }, true)
+            .field(t("proposal.reason", &[]), clip(&p.reason, 1024), false)
+            .field(t("proposal.approvals", &[]), format!("{}/{}", p.approvers.len(), self.config.quorum(&p.tenant)), true);
+        if let Some(d) = p.duration { embed = embed.field(t("proposal.duration", &[]), format_duration(d), true); }
+        if let Some(n) = p.appeal { embed = embed.field(t("proposal.appeal_of", &[]), t("proposal.appeal_case", &[("appeal", &n.to_string())]), true); }
+        if let Some(r) = &p.role { embed = embed.field(t("proposal.role", &[]), if p.origin == Platform::Discord { format!("<@&{}>", r) } else { r.clone() }, true); }
+        if p.delete_days > 0 { embed = embed.field(t("proposal.delete", &[]), t("proposal.delete_days", &[("days", &p.delete_days.to_string())]), true); }
+        if !p.evidence.is_empty() { embed = embed.field(t("proposal.evidence", &[]), clip(&p.evidence, 1024), false); }
+        if !p.priors.is_empty() {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            let mut lines: ::std::vec::Vec<_> = p.priors.iter().rev().take(5).map(|i| format!("• {}", self.config.templates.infraction(self.config.lang(&p.tenant, &Platform::Discord), &Platform::Discord, i, now))).collect();
+            if p.priors.len() > 5 { lines.push(t("proposal.priors_more", &[("count", &(p.priors.len() - 5).to_string())])); }
+            embed = embed.field(t("proposal.priors", &[("count", &p.priors.len().to_string())]), clip(&lines.join("\n"), 1024), false);
+        }
+        embed
     }
 }
 
 #[::async_trait::async_trait]
 impl Driver for Discord {
-    async fn notify(&self, p: &Proposal) -> Result<()> {
-        let ch = if p.origin == Platform::Discord { ChannelId::new(p.channel.parse().unwrap_or(self.log.get())) } else { self.log };
-        let embed = CreateEmbed::new().title(format!("{} Proposal", p.action))
-            .field("Target", format!("<@{}>", p.target.discord.as_deref().unwrap_or(&p.target.raw)), true)
-            .field("Reason", &p.reason, false)
-            .field("Approvals", format!("{}/{}", p.approvers.len(), self.config.required_approvals), true);
-        let btn_ok = CreateButton::new(format!("ok:{}", p.target.raw)).label("Confirm").style(ButtonStyle::Success);
-        let btn_no = CreateButton::new(format!("no:{}", p.target.raw)).label("Cancel").style(ButtonStyle::Danger);
-        let _ = ch.send_message(&self.http, CreateMessage::new().embed(embed).components(vec![CreateActionRow::Buttons(vec![btn_ok, btn_no])])).await;
+    fn platform(&self) -> Platform { Platform::Discord }
+
+    async fn notify(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>> {
+        let Some(ch) = self.channel(p) else { return Ok(None); };
+        let btn_ok = CreateButton::new(format!("ok:{}", p.case)).label(self.text("proposal.confirm", p, &[])).style(ButtonStyle::Success);
+        let btn_no = CreateButton::new(format!("no:{}", p.case)).label(self.text("proposal.cancel", p, &[])).style(ButtonStyle::Danger);
+        let msg = ch.send_message(&self.http, CreateMessage::new().embed(self.embed(p)).components(vec![CreateActionRow::Buttons(vec![btn_ok, btn_no])])).await?;
+        if self.config.discussion.mirror || self.space(p).is_some_and(|s| s.review.thread) {
+            if let Err(e) = ch.create_thread_from_message(&self.http, msg.id, CreateThread::new(self.text("proposal.thread", p, &[]))).await { ::tracing::error!("Discord thread for case {}: {}", p.case, e); }
+        }
+        if let Some(origin) = self.origin(p).filter(|o| p.origin == Platform::Discord && *o != ch) {
+            let _ = origin.say(&self.http, self.text("proposal.ack", p, &[])).await;
+        }
+        Ok(Some(format!("{}:{}", ch, msg.id)))
+    }
+
+    async fn refresh(&self, p: &Proposal, post: &str) -> Result<()> {
+        let (ch, msg) = post.split_once(':').and_then(|(c, m)| Some((c.parse::<u64>().ok()?, m.parse::<u64>().ok()?))).ok_or_else(|| ::anyhow::anyhow!("Malformed post reference {}", post))?;
+        ChannelId::new(ch).edit_message(&self.http, MessageId::new(msg), EditMessage::new().embed(self.embed(p))).await?;
         Ok(())
     }
 
-    async fn execute(&self, p: &Proposal, app: &str) -> Result<()> {
-        let ch = if p.origin == Platform::Discord { ChannelId::new(p.channel.parse().unwrap_or(self.log.get())) } else { self.log };
-        if let Some(did) = &p.target.discord {
-            if let Ok(uid_val) = did.parse::<u64>() {
-                if let Ok(c) = ch.to_channel(&self.http).await {
-                    if let Some(g) = c.guild() {
-                        let audit = format!("Req: {} App: {}", p.author, app);
-                        let uid = UserId::new(uid_val);
-                        if p.action == "ban" { let _ = g.guild_id.ban_with_reason(&self.http, uid, 0, &audit).await; }
-                        if p.action == "kick" { let _ = g.guild_id.kick_with_reason(&self.http, uid, &audit).await; }
+    async fn execute(&self, p: &Proposal, app: &str) -> Result<Report> {
+        let (Some(ch), Some(g)) = (self.channel(p), self.guild(p)) else { return Ok(Report::new()); };
+        let audit = clip(&format!("Case #{} | {} | Req: {} App: {}", p.case, p.reason, p.author, app), 512);
+        let (mut report, mut lines) = (Report::new(), ::std::vec::Vec::new());
+        for (raw, uid) in p.subjects().into_iter().filter_map(|i| Some((&i.raw, i.discord.as_deref()?.parse::<u64>().ok()?))) {
+            if !report.is_empty() { ::tokio::time::sleep(::std::time::Duration::from_millis(self.config.bulk_delay_ms)).await; }
+            let res = match p.action.as_str() {
+                "ban" => g.ban_with_reason(&self.http, UserId::new(uid), p.delete_days, &audit).await,
+                "kick" => g.kick_with_reason(&self.http, UserId::new(uid), &audit).await,
+                "appeal" => g.unban(&self.http, UserId::new(uid)).await,
+                "warn" => UserId::new(uid).direct_message(&self.http, CreateMessage::new().content(self.text("result.warned", p, &[]))).await.map(|_| ()),
+                "role-add" | "role-remove" if p.origin == Platform::Discord => {
+                    let Some(role) = p.role.as_deref().and_then(|r| r.parse::<u64>().ok()) else { continue; };
+                    match p.action.as_str() {
+                        "role-add" => self.http.add_member_role(g, UserId::new(uid), RoleId::new(role), Some(&audit)).await,
+                        _ => self.http.remove_member_role(g, UserId::new(uid), RoleId::new(role), Some(&audit)).await,
                     }
                 }
+                _ => continue,
+            };
+            lines.push(match &res { Ok(_) => format!("✅ <@{}>", uid), Err(e) => format!("❌ <@{}>: {}", uid, e) });
+            report.push((raw.clone(), res.map_err(|e| format!("<@{}>: {}", uid, e))));
+        }
+        let done = report.iter().filter(|(_, r)| r.is_ok()).count();
+        if p.batch.is_empty() {
+            if done == report.len() { let _ = ch.say(&self.http, self.text("result.executed", p, &[("approver", &Platform::Discord.mention(app))])).await; }
+        } else {
+            let head = self.text("result.bulk", p, &[("done", &done.to_string()), ("total", &report.len().to_string()), ("approver", &Platform::Discord.mention(app))]);
+            for chunk in chunk_lines(&head, &lines, 1900) {
+                let _ = ch.say(&self.http, chunk).await;
             }
         }
-        let _ = ch.say(&self.http, format!("✅ Executed {} on {}", p.action, p.target.raw)).await;
-        Ok(())
+        Ok(report)
     }
 
     async fn discard(&self, p: &Proposal, reason: &str) -> Result<()> {
-        let ch = if p.origin == Platform::Discord { ChannelId::new(p.channel.parse().unwrap_or(self.log.get())) } else { self.log };
-        let _ = ch.say(&self.http, format!("🚫 {} proposal for {} discarded: {}", p.action, p.target.raw, reason)).await;
+        if let Some(ch) = self.channel(p) { let _ = ch.say(&self.http, self.text("result.discarded", p, &[("status", reason)])).await; }
+        for (_, post) in p.posts.iter().filter(|(pl, _)| *pl == Platform::Discord) {
+            let Some((c, m)) = post.split_once(':').and_then(|(c, m)| Some((c.parse::<u64>().ok()?, m.parse::<u64>().ok()?))) else { continue; };
+            let _ = ChannelId::new(c).edit_message(&self.http, MessageId::new(m), EditMessage::new().components(vec![])).await;
+        }
+        Ok(())
+    }
+
+    async fn revert(&self, p: &Proposal) -> Result<()> {
+        let Some(guild) = self.guild(p).filter(|_| p.action == "ban") else { return Ok(()); };
+        for uid in p.subjects().into_iter().filter_map(|i| i.discord.as_deref()?.parse::<u64>().ok()) {
+            if let Err(e) = guild.unban(&self.http, UserId::new(uid)).await { ::tracing::error!("Discord unban {}: {}", uid, e); }
+        }
+        Ok(())
+    }
+
+    async fn alert(&self, p: &Proposal, text: &str) -> Result<()> {
+        let Some(log) = self.space(p).and_then(|s| s.log.parse::<u64>().ok()) else { return Ok(()); };
+        ChannelId::new(log).say(&self.http, text).await?;
+        Ok(())
+    }
+
+    async fn relay(&self, p: &Proposal, c: &Comment) -> Result<::std::option::Option<::std::string::String>> {
+        let Some(thread) = p.posts.iter().find(|(pl, _)| *pl == Platform::Discord).and_then(|(_, post)| post.rsplit(':').next()?.parse::<u64>().ok()) else { return Ok(None); };
+        let text = self.config.text(&p.tenant, &Platform::Discord, "discussion.relay", &[("author", &Platform::Discord.mention(&c.author)), ("platform", &format!("{:?}", c.origin)), ("text", &c.text)]);
+        Ok(Some(ChannelId::new(thread).say(&self.http, text).await?.id.to_string()))
+    }
+
+    async fn message_user(&self, user: &Identity, text: &str) -> Result<()> {
+        let Some(uid) = user.discord.as_deref().and_then(|d| d.parse::<u64>().ok()) else { return Ok(()); };
+        UserId::new(uid).direct_message(&self.http, CreateMessage::new().content(text)).await?;
+        Ok(())
+    }
+
+    async fn lockdown(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>> {
+        let (Some(space), Some(guild)) = (self.space(p), self.guild(p)) else { return Ok(None); };
+        let everyone = PermissionOverwriteType::Role(RoleId::new(guild.get()));
+        let lock = Permissions::SEND_MESSAGES | Permissions::SEND_MESSAGES_IN_THREADS;
+        let snap = if space.lockdown.is_empty() {
+            let prior = guild.to_partial_guild(&self.http).await?.verification_level;
+            guild.edit(&self.http, EditGuild::new().verification_level(VerificationLevel::Higher)).await?;
+            Snapshot::Verification(prior.into())
+        } else {
+            let (mut prior, mut failed) = (::std::vec::Vec::new(), ::std::vec::Vec::new());
+            for id in space.lockdown.iter().filter_map(|c| c.parse::<u64>().ok()) {
+                let ch = match ChannelId::new(id).to_channel(&self.http).await.map(|c| c.guild()) {
+                    Ok(Some(ch)) => ch,
+                    Ok(None) => { failed.push(format!("{}: not a guild channel", id)); continue; }
+                    Err(e) => { failed.push(format!("{}: {}", id, e)); continue; }
+                };
+                let old = ch.permission_overwrites.iter().find(|o| o.kind == everyone).map(|o| (o.allow.bits(), o.deny.bits()));
+                let (allow, deny) = old.unwrap_or((0, 0));
+                let overwrite = PermissionOverwrite { allow: Permissions::from_bits_truncate(allow) - lock, deny: Permissions::from_bits_truncate(deny) | lock, kind: everyone };
+                match ch.create_permission(&self.http, overwrite).await {
+                    Ok(_) => prior.push((id, old)),
+                    Err(e) => failed.push(format!("{}: {}", id, e)),
+                }
+            }
+            if !failed.is_empty() {
+                if let Err(e) = self.restore(p, &::serde_json::to_string(&Snapshot::Channels(prior))?).await { ::tracing::error!("Discord lockdown rollback: {}", e); }
+                ::anyhow::bail!("lockdown failed on channel(s) {}", failed.join("; "));
+            }
+            Snapshot::Channels(prior)
+        };
+        Ok(Some(::serde_json::to_string(&snap)?))
+    }
+
+    async fn restore(&self, p: &Proposal, snapshot: &str) -> Result<()> {
+        let Some(guild) = self.guild(p) else { return Ok(()); };
+        let everyone = PermissionOverwriteType::Role(RoleId::new(guild.get()));
+        match ::serde_json::from_str(snapshot)? {
+            Snapshot::Verification(level) => { guild.edit(&self.http, EditGuild::new().verification_level(VerificationLevel::from(level))).await?; }
+            Snapshot::Channels(prior) => {
+                let mut failed = ::std::vec::Vec::new();
+                for (id, old) in prior {
+                    let res = match old {
+                        Some((allow, deny)) => ChannelId::new(id).create_permission(&self.http, PermissionOverwrite { allow: Permissions::from_bits_truncate(allow), deny: Permissions::from_bits_truncate(deny), kind: everyone }).await,
+                        None => ChannelId::new(id).delete_permission(&self.http, everyone).await,
+                    };
+                    if let Err(e) = res { failed.push(format!("{}: {}", id, e)); }
+                }
+                ::anyhow::ensure!(failed.is_empty(), "unlock failed on channel(s) {}", failed.join("; "));
+            }
+        }
         Ok(())
     }
 }
\ No newline at end of file
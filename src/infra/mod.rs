@@ -1,3 +1,4 @@
+pub mod command;
 pub mod discord;
 pub mod stoat;
 pub mod store;
\ No newline at end of file
@@ -1,46 +1,111 @@
-use crate::config::AppConfig;
-use crate::domain::{models::*, ports::*};
+use super::command;
+use crate::config::{AppConfig, SpaceConfig, TenantConfig};
+use crate::domain::{models::*, ports::*, raid::RaidWatch};
 use ::anyhow::{Context, Result};
 use ::futures_util::{SinkExt, StreamExt};
 use ::reqwest::Client;
 use ::serde_json::{json, Value};
 use ::std::{collections::HashMap, sync::Arc};
-use ::tokio::sync::{mpsc, RwLock};
+use ::std::time::{SystemTime, UNIX_EPOCH};
+use ::tokio::sync::{mpsc, oneshot, Mutex, RwLock};
 
+const SEND_MESSAGE: i64 = 1 << 22;
+
+#[derive(Clone)]
 pub struct Stoat {
     http: Client,
     token: ::std::string::String,
-    log: ::std::string::String,
-    props: Arc<RwLock<HashMap<::std::string::String, ::std::string::String>>>,
+    bot_id: ::std::string::String,
+    tx: mpsc::Sender<Envelope>,
+    props: Arc<RwLock<HashMap<::std::string::String, (::std::string::String, ::std::string::String)>>>,
+    servers: HashMap<::std::string::String, ::std::string::String>,
+    channels: Arc<RwLock<HashMap<::std::string::String, ::std::string::String>>>,
+    watch: Arc<HashMap<::std::string::String, Mutex<RaidWatch>>>,
+    config: Arc<AppConfig>,
 }
 
 impl Stoat {
-    pub async fn new(token: &str, log: &str, staff: &str, tx: mpsc::Sender<Event>, config: Arc<AppConfig>) -> Result<Self> {
+    pub async fn new(token: &str, tx: mpsc::Sender<Envelope>, config: Arc<AppConfig>) -> Result<Self> {
         let http = Client::builder().user_agent("Urchin").danger_accept_invalid_certs(true).build()?;
         
         let me_res = http.get("https://stoat.chat/api/users/@me").header("x-bot-token", token).send().await?.error_for_status()?;
         let me: Value = me_res.json().await?;
-        let my_id = me["_id"].as_str().context("Failed to get bot ID")?.to_string();
+        let bot_id = me["_id"].as_str().context("Failed to get bot ID")?.to_string();
 
-        let props = Arc::new(RwLock::new(HashMap::new()));
-        let (tk, ht, st, pr, txc, cfg, bot_id) = (token.to_string(), http.clone(), staff.to_string(), props.clone(), tx, config, my_id);
-        
+        let watch = config.tenants.iter().filter(|t| t.stoat.is_some()).filter_map(|t| Some((t.name.clone(), Mutex::new(RaidWatch::new(config.raid.clone()?))))).collect();
+        let mut stoat = Self { http, token: token.into(), bot_id, tx, props: Arc::new(RwLock::new(HashMap::new())), servers: HashMap::new(), channels: Arc::new(RwLock::new(HashMap::new())), watch: Arc::new(watch), config: Arc::clone(&config) };
+        for t in &config.tenants {
+            let Some(space) = &t.stoat else { continue; };
+            let sid = if space.id.is_empty() { stoat.server_of(&space.log).await.with_context(|| format!("Resolving the Stoat server of tenant {}", t.name))? } else { space.id.clone() };
+            stoat.servers.insert(t.name.clone(), sid);
+        }
+        let gw = stoat.clone();
         ::tokio::spawn(async move {
             loop {
-                if let Err(e) = Self::listen(&ht, &tk, &st, &bot_id, &txc, &pr, &cfg).await { ::tracing::error!("Stoat WS: {}", e); }
+                if let Err(e) = gw.listen().await { ::tracing::error!("Stoat WS: {}", e); }
                 ::tokio::time::sleep(::std::time::Duration::from_secs(5)).await;
             }
         });
 
-        Ok(Self { http, token: token.into(), log: log.into(), props })
+        Ok(stoat)
+    }
+
+    fn t(&self, tenant: &str, key: &str, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.text(tenant, &Platform::Stoat, key, vars)
+    }
+
+    fn text(&self, key: &str, p: &Proposal, vars: &[(&str, &str)]) -> ::std::string::String {
+        self.config.templates.proposal(self.config.lang(&p.tenant, &Platform::Stoat), &Platform::Stoat, key, p, vars)
+    }
+
+    fn render(&self, p: &Proposal) -> ::std::string::String {
+        let t = |key: &str, vars: &[(&str, &str)]| self.text(key, p, vars);
+        let target = match p.batch.len() {
+            0 => p.target.stoat.as_deref().unwrap_or(&p.target.raw).to_string(),
+            n => t("proposal.batch", &[("count", &n.to_string()), ("list", &p.batch.iter().take(20).map(|i| i.raw.as_str()).collect::<::std::vec::Vec<_>>().join(", "))]),
+        };
+        let mut msg = format!("{}\n", t("proposal.header", &[]));
+        let mut line = |label: &str, value: &str| msg.push_str(&format!("{}: {}\n", t(label, &[]), value));
+        line("proposal.target", &target);
+        line("proposal.requester", &Platform::Stoat.mention(&p.author));
+        line("proposal.reason", &clip(&p.reason, 800));
+        if let Some(d) = p.duration { line("proposal.duration", &format_duration(d)); }
+        if let Some(n) = p.appeal { line("proposal.appeal_of", &t("proposal.appeal_case", &[("appeal", &n.to_string())])); }
+        if let Some(r) = &p.role { line("proposal.role", r); }
+        if p.delete_days > 0 { line("proposal.delete", &t("proposal.delete_days", &[("days", &p.delete_days.to_string())])); }
+        if !p.evidence.is_empty() { line("proposal.evidence", &clip(&p.evidence, 400)); }
+        if !p.priors.is_empty() {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            msg.push_str(&format!("{}:\n", t("proposal.priors", &[("count", &p.priors.len().to_string())])));
+            for i in p.priors.iter().rev().take(5) { msg.push_str(&format!("• {}\n", clip(&self.config.templates.infraction(self.config.lang(&p.tenant, &Platform::Stoat), &Platform::Stoat, i, now), 120))); }
+        }
+        let r = &self.config.reactions;
+        let shown: ::std::vec::Vec<_> = [&r.approve, &r.reject, &r.abstain, &r.cancel].iter().map(|e| Self::emoji(e)).collect();
+        msg.push_str(&t("proposal.react", &[("approve", &shown[0]), ("reject", &shown[1]), ("abstain", &shown[2]), ("cancel", &shown[3])]));
+        clip(&msg, 2000)
+    }
+
+    fn emoji(id: &str) -> ::std::string::String {
+        if id.len() == 26 && id.chars().all(|c| c.is_ascii_alphanumeric()) { format!(":{}:", id) } else { id.into() }
+    }
+
+    async fn say(&self, ch: &str, content: &str) -> Result<()> {
+        self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": content})).send().await?.error_for_status()?;
+        Ok(())
     }
 
-    async fn listen(http: &Client, tk: &str, staff: &str, bot_id: &str, tx: &mpsc::Sender<Event>, props: &Arc<RwLock<HashMap<::std::string::String, ::std::string::String>>>, cfg: &Arc<AppConfig>) -> Result<()> {
+    async fn set_default_permissions(&self, cid: &str, allow: i64, deny: i64) -> Result<()> {
+        self.http.put(format!("https://stoat.chat/api/channels/{}/permissions/default", cid)).header("x-bot-token", &self.token)
+            .json(&json!({"permissions": {"allow": allow, "deny": deny}})).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    async fn listen(&self) -> Result<()> {
         let tls = ::native_tls::TlsConnector::builder().danger_accept_invalid_certs(true).build()?;
         let (ws, _) = ::tokio_tungstenite::connect_async_tls_with_config("wss://stoat.chat/events", None, false, Some(::tokio_tungstenite::Connector::NativeTls(tls))).await?;
         let (mut w, mut r) = ws.split();
 
-        w.send(::tokio_tungstenite::tungstenite::Message::Text(json!({"type": "Authenticate", "token": tk}).to_string())).await?;
+        w.send(::tokio_tungstenite::tungstenite::Message::Text(json!({"type": "Authenticate", "token": self.token}).to_string())).await?;
         let mut hb = ::tokio::time::interval(::std::time::Duration::from_secs(20));
 
         loop {
@@ -49,9 +114,15 @@ impl Stoat {
                 msg = r.next() => {
                     let pl: Value = ::serde_json::from_str(&msg.context("WS End")??.into_text()?)?;
                     match pl["type"].as_str() {
-                        Some("Authenticated") => { w.send(::tokio_tungstenite::tungstenite::Message::Text(json!({"type": "UpdateUser", "data": {"status": {"presence": "Online"}}}).to_string())).await?; }
-                        Some("MessageReact") => { Self::on_react(&pl, http, tk, staff, bot_id, tx, props).await?; }
-                        Some("Message") => { Self::on_msg(&pl, &cfg.command_prefix, tx).await?; }
+                        Some("Authenticated") => {
+                            let me = self.clone();
+                            ::tokio::spawn(async move { me.recover().await });
+                            w.send(::tokio_tungstenite::tungstenite::Message::Text(json!({"type": "UpdateUser", "data": {"status": {"presence": "Online"}}}).to_string())).await?;
+                        }
+                        Some("MessageReact") => { self.on_react(&pl, false).await?; }
+                        Some("MessageUnreact") => { self.on_react(&pl, true).await?; }
+                        Some("Message") => { self.on_msg(&pl).await?; }
+                        Some("ServerMemberJoin") => { self.on_join(&pl).await?; }
                         _ => {}
                     }
                 }
@@ -59,74 +130,451 @@ impl Stoat {
         }
     }
 
-    async fn on_msg(pl: &Value, prefix: &str, tx: &mpsc::Sender<Event>) -> Result<()> {
-        let content = pl["content"].as_str().unwrap_or("");
-        let parts: ::std::vec::Vec<&str> = content.split_whitespace().collect();
-        if parts.len() >= 3 && parts[0].starts_with(prefix) {
-            tx.send(Event::Propose {
-                action: parts[0][prefix.len()..].to_string(),
-                target: parts[1].to_string(),
-                reason: parts[2..].join(" "),
-                author: pl["author"].as_str().unwrap_or("?").into(),
-                origin: Platform::Stoat,
-                channel: pl["channel"].as_str().unwrap_or("?").into()
-            }).await?;
+    async fn on_join(&self, pl: &Value) -> Result<()> {
+        let Some((tenant, space)) = pl["id"].as_str().and_then(|sid| self.tenant_by_server(sid)) else { return Ok(()); };
+        let (Some(watch), Some(uid)) = (self.watch.get(&tenant.name), pl["user"].as_str()) else { return Ok(()); };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if let Some(suspects) = watch.lock().await.join(uid, now) {
+            let reason = self.t(&tenant.name, "raid.joins", &[("count", &suspects.len().to_string()), ("seconds", &self.config.raid.as_ref().map_or(0, |r| r.join_window_seconds).to_string())]);
+            self.tx.send((raid_proposal(tenant.name.clone(), suspects, reason, Platform::Stoat, space.log.clone()), None)).await?;
+        }
+        Ok(())
+    }
+
+    async fn on_flood(&self, pl: &Value) -> Result<()> {
+        let Some(uid) = pl["author"].as_str().filter(|uid| *uid != self.bot_id && !self.watch.is_empty()) else { return Ok(()); };
+        let Some((tenant, space)) = self.tenant(pl["channel"].as_str().unwrap_or("?")).await else { return Ok(()); };
+        let Some(watch) = self.watch.get(&tenant.name) else { return Ok(()); };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        if let Some(suspects) = watch.lock().await.message(uid, pl["content"].as_str().unwrap_or(""), now) {
+            let reason = self.t(&tenant.name, "raid.flood", &[("count", &suspects.len().to_string())]);
+            self.tx.send((raid_proposal(tenant.name.clone(), suspects, reason, Platform::Stoat, space.log.clone()), None)).await?;
         }
         Ok(())
     }
 
-    async fn on_react(pl: &Value, http: &Client, tk: &str, staff: &str, bot_id: &str, tx: &mpsc::Sender<Event>, props: &Arc<RwLock<HashMap<::std::string::String, ::std::string::String>>>) -> Result<()> {
-        let is_ok = pl["emoji_id"].as_str().is_some_and(|e| e.contains('✅'));
-        let is_no = pl["emoji_id"].as_str().is_some_and(|e| e.contains('❌'));
-        if !is_ok && !is_no { return Ok(()); }
+    async fn on_reply(&self, pl: &Value) -> Result<()> {
+        let refs: ::std::vec::Vec<::std::string::String> = pl["replies"].as_array().map(|r| r.iter().filter_map(|r| r.as_str().map(::std::string::String::from)).collect()).unwrap_or_default();
+        let (cid, author, content) = (pl["channel"].as_str().unwrap_or("?"), pl["author"].as_str().unwrap_or("?"), pl["content"].as_str().unwrap_or(""));
+        if !self.config.discussion.mirror || refs.is_empty() || author == self.bot_id || content.is_empty() || content.starts_with(self.config.command_prefix.as_str()) { return Ok(()); }
+        let Some((tenant, space)) = self.tenant(cid).await else { return Ok(()); };
+        if self.capabilities(cid, author, space).await.is_empty() { return Ok(()); }
+        let ev = Event::Comment { tenant: tenant.name.clone(), origin: Platform::Stoat, author: Platform::Stoat.actor(author), text: content.into(), refs, post: pl["_id"].as_str().unwrap_or("").into() };
+        self.tx.send((ev, None)).await?;
+        Ok(())
+    }
+
+    fn answer(&self, cid: &str, mid: &str, rx: oneshot::Receiver<Outcome>) {
+        let (http, tk, cid, mid) = (self.http.clone(), self.token.clone(), cid.to_string(), mid.to_string());
+        ::tokio::spawn(async move {
+            let Ok(res) = rx.await else { return; };
+            if res.as_ref().is_ok_and(|s| s.is_empty()) { return; }
+            let content = clip(&res.unwrap_or_else(|e| format!("❌ {}", e)), 2000);
+            let _ = http.post(format!("https://stoat.chat/api/channels/{}/messages", cid)).header("x-bot-token", &tk).json(&json!({"content": content, "replies": [{"id": mid, "mention": false}]})).send().await;
+        });
+    }
+
+    async fn on_msg(&self, pl: &Value) -> Result<()> {
+        self.on_flood(pl).await?;
+        self.on_reply(pl).await?;
+        let (cid, author, mid) = (pl["channel"].as_str().unwrap_or("?"), pl["author"].as_str().unwrap_or("?"), pl["_id"].as_str().unwrap_or(""));
+        if author == self.bot_id { return Ok(()); }
+        let Some(parsed) = command::parse(pl["content"].as_str().unwrap_or(""), &self.config.command_prefix) else { return Ok(()); };
+        let found = self.tenant(cid).await;
+        let tenant = found.map_or("", |(t, _)| t.name.as_str());
+        let (reply, rx) = oneshot::channel();
+        let cmd = match parsed {
+            Ok(cmd) if cmd.name == "help" => { let _ = reply.send(Ok(self.help(tenant, cmd.args.first().map(::std::string::String::as_str)))); self.answer(cid, mid, rx); return Ok(()); }
+            Ok(cmd) => cmd,
+            Err(e) => { let _ = reply.send(Err(self.syntax(tenant, e))); self.answer(cid, mid, rx); return Ok(()); }
+        };
+        let (action, args, flags) = (cmd.name.as_str(), cmd.args(), cmd.flags());
+        if action == "appeal" {
+            match self.appeal(pl, &args).await {
+                Ok(ev) => self.tx.send((ev, Some(reply))).await?,
+                Err(e) => { let _ = reply.send(Err(e)); }
+            }
+            self.answer(cid, mid, rx);
+            return Ok(());
+        }
+        let Some((tenant, space)) = found else { return Ok(()); };
+        let caps = self.capabilities(cid, author, space).await;
+        if caps.is_empty() { return Ok(()); }
+        let tenant = tenant.name.clone();
+        if action == "pending" {
+            let me = self.clone();
+            ::tokio::spawn(async move { let _ = reply.send(pending(&me.tx, &tenant).await.map(|open| me.pending_text(&tenant, &open))); });
+            self.answer(cid, mid, rx);
+            return Ok(());
+        }
+        if ["infractions", "case", "status", "approve", "reject", "cancel", "amend", "link"].contains(&action) {
+            let target = args[0].to_string();
+            let ev = match action {
+                "infractions" => Ok(Event::History { tenant, target, origin: Platform::Stoat, trail: false }),
+                "case" | "status" => Ok(Event::Case { tenant, reference: target, origin: Platform::Stoat, trail: action == "status" }),
+                "reject" => Ok(Event::Reject { tenant, target, voter: Platform::Stoat.actor(author), origin: Platform::Stoat, caps }),
+                "cancel" => Ok(Event::Cancel { tenant, target, author: Platform::Stoat.actor(author), origin: Platform::Stoat, caps }),
+                "amend" => match flags.get("duration").map(|d| parse_duration(d).ok_or(d)).transpose() {
+                    Ok(duration) => Ok(Event::Amend { tenant, target, author: Platform::Stoat.actor(author), origin: Platform::Stoat, action: flags.get("action").map(|a| a.to_string()), reason: Some(args[1..].join(" ")), duration, caps }),
+                    Err(d) => Err(self.t(&tenant, "form.bad_duration", &[("value", d)])),
+                },
+                "link" => Ok(Event::Link { tenant, discord: target, stoat: args[1].to_string(), author: Platform::Stoat.actor(author), origin: Platform::Stoat, caps }),
+                _ => Ok(Event::Approve { tenant, target, approver: Platform::Stoat.actor(author), origin: Platform::Stoat, caps }),
+            };
+            match ev {
+                Ok(ev) => self.tx.send((ev, Some(reply))).await?,
+                Err(e) => { let _ = reply.send(Err(e)); }
+            }
+            self.answer(cid, mid, rx);
+            return Ok(());
+        }
+        match self.proposal(pl, &tenant, action, &args, &flags).await {
+            Ok(draft) => self.tx.send((Event::Propose(Draft { tenant, caps, ..draft }), Some(reply))).await?,
+            Err(e) => { let _ = reply.send(Err(e)); }
+        }
+        self.answer(cid, mid, rx);
+        Ok(())
+    }
+
+    fn pending_text(&self, tenant: &str, open: &[Proposal]) -> ::std::string::String {
+        if open.is_empty() { return self.t(tenant, "pending.none", &[]); }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let lines: ::std::vec::Vec<_> = open.iter().take(20).map(|p| self.config.templates.pending(self.config.lang(tenant, &Platform::Stoat), &Platform::Stoat, p, self.config.quorum(tenant), now)).collect();
+        let mut out = format!("{}\n{}", self.t(tenant, "pending.header", &[("count", &open.len().to_string())]), lines.join("\n"));
+        if open.len() > 20 { out.push_str(&format!("\n{}", self.t(tenant, "pending.more", &[("count", &(open.len() - 20).to_string())]))); }
+        out
+    }
+
+    async fn recover(&self) {
+        for tenant in self.servers.keys() {
+            let open = match pending(&self.tx, tenant).await {
+                Ok(open) => open,
+                Err(e) => { ::tracing::error!("Stoat recovering proposals of {}: {}", tenant, e); continue; }
+            };
+            let mut props = self.props.write().await;
+            for p in open {
+                for (_, post) in p.posts.iter().filter(|(pl, _)| *pl == Platform::Stoat) {
+                    if let Some((_, m)) = post.split_once(':') { props.insert(m.into(), (tenant.clone(), p.case.to_string())); }
+                }
+            }
+        }
+    }
+
+    fn usage(&self, tenant: &str, name: &str) -> ::std::string::String {
+        self.t(tenant, &format!("usage.{}", name), &[("prefix", &self.config.command_prefix)])
+    }
+
+    fn help(&self, tenant: &str, name: ::std::option::Option<&str>) -> ::std::string::String {
+        match name.map(|n| n.trim_start_matches(self.config.command_prefix.as_str())).filter(|n| command::COMMANDS.iter().any(|(c, _)| c == n)) {
+            Some(n) => self.usage(tenant, n),
+            None => ::std::iter::once(self.t(tenant, "help.header", &[])).chain(command::COMMANDS.iter().map(|(c, _)| format!("• {}", self.usage(tenant, c)))).collect::<::std::vec::Vec<_>>().join("\n"),
+        }
+    }
+
+    fn syntax(&self, tenant: &str, e: command::SyntaxError) -> ::std::string::String {
+        match e {
+            command::SyntaxError::Unterminated => self.t(tenant, "error.unterminated", &[]),
+            command::SyntaxError::MissingValue(f) => self.t(tenant, "error.flag_value", &[("flag", &f)]),
+            command::SyntaxError::UnknownFlag(f) => self.t(tenant, "error.unknown_flag", &[("flag", &f)]),
+            command::SyntaxError::Arity(n) => self.t(tenant, "error.usage", &[("usage", &self.usage(tenant, &n))]),
+        }
+    }
+
+    async fn appeal(&self, pl: &Value, args: &[&str]) -> ::std::result::Result<Event, ::std::string::String> {
+        let cid = pl["channel"].as_str().unwrap_or("?");
+        let chan: Value = self.http.get(format!("https://stoat.chat/api/channels/{}", cid)).header("x-bot-token", &self.token).send().await.map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
+        if chan["channel_type"].as_str() != Some("DirectMessage") { return Err(self.t("", "appeal.dm_only", &[])); }
+        let usage = || self.t("", "appeal.usage", &[("prefix", &self.config.command_prefix)]);
+        let (name, case, reason) = appeal_args(args).ok_or_else(usage)?;
+        let (tenant, space) = self.config.appeal_tenant(&Platform::Stoat, name).and_then(|t| Some((t, t.stoat.as_ref()?))).ok_or_else(usage)?;
+        let author = pl["author"].as_str().unwrap_or("?");
+        Ok(Event::Propose(Draft { tenant: tenant.name.clone(), action: "appeal".into(), target: author.into(), author: Platform::Stoat.actor(author), origin: Platform::Stoat, channel: space.log.clone(), reason, appeal: Some(case), ..Default::default() }))
+    }
+
+    async fn proposal(&self, pl: &Value, tenant: &str, action: &str, args: &[&str], flags: &HashMap<&str, &str>) -> ::std::result::Result<Draft, ::std::string::String> {
+        let delete_days = match flags.get("delete") {
+            None => 0,
+            Some(d) => d.trim_end_matches('d').parse::<u8>().map_err(|_| self.t(tenant, "error.delete_flag", &[]))?,
+        };
+        let duration = flags.get("duration").map(|d| parse_duration(d).ok_or_else(|| self.t(tenant, "form.bad_duration", &[("value", d)]))).transpose()?;
+        let cid = pl["channel"].as_str().unwrap_or("?");
+        let emergency = action == "emergency";
+        let mut role = None;
+        let (action, target, batch, reason) = if SERVER_ACTIONS.contains(&action) {
+            (action, "", ::std::vec::Vec::new(), args.join(" "))
+        } else if ROLE_ACTIONS.contains(&action) {
+            let outranks = self.outranks(cid, pl["author"].as_str().unwrap_or("?"), args[1]).await.map_err(|e| e.to_string())?;
+            if !outranks { return Err(self.t(tenant, "error.role_rank", &[])); }
+            role = Some(args[1].to_string());
+            (action, args[0], ::std::vec::Vec::new(), args[2..].join(" "))
+        } else if emergency {
+            (args[0], args[1], ::std::vec::Vec::new(), args[2..].join(" "))
+        } else if action == "bulk" {
+            let mut batch: ::std::vec::Vec<_> = args[1..].iter().map(|a| a.to_string()).take_while(|a| a.len() == 26 && a.chars().all(|c| c.is_ascii_alphanumeric())).collect();
+            let reason = args[1 + batch.len()..].join(" ");
+            for a in pl["attachments"].as_array().into_iter().flatten() {
+                let url = format!("https://autumn.stoat.chat/{}/{}", a["tag"].as_str().unwrap_or("attachments"), a["_id"].as_str().unwrap_or(""));
+                let text = self.http.get(url).send().await.map_err(|e| e.to_string())?.text().await.map_err(|e| e.to_string())?;
+                batch.extend(text.split(|c: char| !c.is_ascii_alphanumeric()).filter(|t| t.len() == 26).map(::std::string::String::from));
+            }
+            if let Some(mins) = flags.get("joined") {
+                let mins = mins.parse::<i64>().ok().filter(|m| (1..=1440).contains(m)).ok_or_else(|| self.t(tenant, "error.joined_flag", &[]))?;
+                batch.extend(self.joined_since(cid, mins).await.map_err(|e| e.to_string())?);
+            }
+            if batch.is_empty() { return Err(self.t(tenant, "error.no_targets", &[])); }
+            (args[0], "", batch, reason)
+        } else {
+            (action, args[0], ::std::vec::Vec::new(), args[1..].join(" "))
+        };
+        Ok(Draft {
+            action: action.into(),
+            target: target.into(),
+            batch,
+            author: Platform::Stoat.actor(pl["author"].as_str().unwrap_or("?")),
+            origin: Platform::Stoat,
+            channel: cid.into(),
+            role,
+            reason,
+            duration,
+            delete_days,
+            emergency,
+            ..Default::default()
+        })
+    }
+
+    async fn dm_channel(&self, uid: &str) -> Result<::std::string::String> {
+        let chan: Value = self.http.get(format!("https://stoat.chat/api/users/{}/dm", uid)).header("x-bot-token", &self.token).send().await?.error_for_status()?.json().await?;
+        Ok(chan["_id"].as_str().context("No DM channel")?.to_string())
+    }
+
+    async fn server_of(&self, cid: &str) -> Result<::std::string::String> {
+        if let Some(sid) = self.channels.read().await.get(cid) { return Ok(sid.clone()); }
+        let chan: Value = self.http.get(format!("https://stoat.chat/api/channels/{}", cid)).header("x-bot-token", &self.token).send().await?.json().await?;
+        let sid = chan["server"].as_str().context("No server")?.to_string();
+        self.channels.write().await.insert(cid.into(), sid.clone());
+        Ok(sid)
+    }
+
+    fn tenant_by_server(&self, sid: &str) -> ::std::option::Option<(&TenantConfig, &SpaceConfig)> {
+        let (name, _) = self.servers.iter().find(|(_, s)| *s == sid)?;
+        self.config.tenant(name).and_then(|t| Some((t, t.stoat.as_ref()?)))
+    }
+
+    async fn tenant(&self, cid: &str) -> ::std::option::Option<(&TenantConfig, &SpaceConfig)> {
+        self.tenant_by_server(&self.server_of(cid).await.ok()?)
+    }
+
+    async fn capabilities(&self, cid: &str, uid: &str, space: &SpaceConfig) -> Capabilities {
+        let roles = self.roles(cid, uid).await.unwrap_or_default();
+        self.config.capabilities(&Platform::Stoat, &roles, &space.roles)
+    }
+
+    fn space(&self, p: &Proposal) -> ::std::option::Option<&SpaceConfig> {
+        self.config.space(&p.tenant, &Platform::Stoat)
+    }
+
+    fn origin(&self, p: &Proposal) -> ::std::option::Option<::std::string::String> {
+        self.config.tenant(&p.tenant)?.route(&Platform::Stoat, &p.origin, &p.channel)
+    }
+
+    fn channel(&self, p: &Proposal) -> ::std::option::Option<::std::string::String> {
+        self.space(p)?.review.channel(&p.action, p.emergency).cloned().or_else(|| self.origin(p))
+    }
+
+    async fn roles(&self, cid: &str, uid: &str) -> Result<::std::vec::Vec<::std::string::String>> {
+        self.member_roles(&self.server_of(cid).await?, uid).await
+    }
+
+    async fn member_roles(&self, sid: &str, uid: &str) -> Result<::std::vec::Vec<::std::string::String>> {
+        let mem: Value = self.http.get(format!("https://stoat.chat/api/servers/{}/members/{}", sid, uid)).header("x-bot-token", &self.token).send().await?.error_for_status()?.json().await?;
+        Ok(mem["roles"].as_array().into_iter().flatten().filter_map(|v| v.as_str().map(::std::string::String::from)).collect())
+    }
+
+    async fn outranks(&self, cid: &str, uid: &str, role: &str) -> Result<bool> {
+        let sid = self.server_of(cid).await?;
+        let server: Value = self.http.get(format!("https://stoat.chat/api/servers/{}", sid)).header("x-bot-token", &self.token).send().await?.error_for_status()?.json().await?;
+        let rank = |r: &str| server["roles"][r]["rank"].as_i64();
+        let best = self.member_roles(&sid, uid).await?.iter().filter_map(|r| rank(r)).min();
+        Ok(matches!((best, rank(role)), (Some(b), Some(r)) if b < r))
+    }
+
+    async fn joined_since(&self, cid: &str, mins: i64) -> Result<::std::vec::Vec<::std::string::String>> {
+        let sid = self.server_of(cid).await?;
+        let res: Value = self.http.get(format!("https://stoat.chat/api/servers/{}/members", sid)).header("x-bot-token", &self.token).send().await?.error_for_status()?.json().await?;
+        let cutoff = mins.checked_mul(60).and_then(|s| ::serenity::model::Timestamp::now().unix_timestamp().checked_sub(s)).context("joined window out of range")?;
+        Ok(res["members"].as_array().into_iter().flatten()
+            .filter(|m| m["joined_at"].as_str().and_then(|j| ::serenity::model::Timestamp::parse(j).ok()).is_some_and(|j| j.unix_timestamp() >= cutoff))
+            .filter_map(|m| m["_id"]["user"].as_str().map(::std::string::String::from))
+            .collect())
+    }
+
+    async fn on_react(&self, pl: &Value, removed: bool) -> Result<()> {
+        let emoji = pl["emoji_id"].as_str().unwrap_or("");
+        let r = &self.config.reactions;
+        if ![&r.approve, &r.reject, &r.abstain, &r.cancel].iter().any(|e| e.as_str() == emoji) { return Ok(()); }
+        if removed && emoji == r.cancel { return Ok(()); }
 
         let uid = pl["user_id"].as_str().context("Missing user_id")?;
-        if uid == bot_id { return Ok(()); } 
+        if uid == self.bot_id { return Ok(()); } 
 
         let mid = pl["id"].as_str().context("Missing id")?;
         let cid = pl["channel_id"].as_str().context("Missing channel_id")?;
         
-        let chan: Value = http.get(format!("https://stoat.chat/api/channels/{}", cid)).header("x-bot-token", tk).send().await?.json().await?;
-        let sid = chan["server"].as_str().context("No server")?;
-        let mem: Value = http.get(format!("https://stoat.chat/api/servers/{}/members/{}", sid, uid)).header("x-bot-token", tk).send().await?.json().await?;
-        
-        if mem["roles"].as_array().is_some_and(|r| r.iter().any(|v| v.as_str() == Some(staff))) {
-            if let Some(target) = props.read().await.get(mid) {
-                if is_ok { tx.send(Event::Approve { target: target.clone(), approver: uid.into() }).await?; }
-                if is_no { 
-                    tx.send(Event::Cancel { target: target.clone(), author: uid.into() }).await?; 
-                    let _ = http.delete(format!("https://stoat.chat/api/channels/{}/messages/{}", cid, mid)).header("x-bot-token", tk).send().await;
-                }
-            }
-        }
+        let Some((tenant, target)) = self.props.read().await.get(mid).cloned() else { return Ok(()); };
+        let Some(space) = self.config.space(&tenant, &Platform::Stoat) else { return Ok(()); };
+        let caps = self.capabilities(cid, uid, space).await;
+        if caps.is_empty() { return Ok(()); }
+        let (reply, rx) = oneshot::channel();
+        let ev = match emoji {
+            e if removed => Event::Withdraw { tenant, target, voter: Platform::Stoat.actor(uid), origin: Platform::Stoat, vote: Some(if e == r.approve { Vote::Approve } else if e == r.reject { Vote::Reject } else { Vote::Abstain }) },
+            e if e == r.approve => Event::Approve { tenant, target, approver: Platform::Stoat.actor(uid), origin: Platform::Stoat, caps },
+            e if e == r.reject => Event::Reject { tenant, target, voter: Platform::Stoat.actor(uid), origin: Platform::Stoat, caps },
+            e if e == r.abstain => Event::Abstain { tenant, target, voter: Platform::Stoat.actor(uid), origin: Platform::Stoat, caps },
+            _ => Event::Cancel { tenant, target, author: Platform::Stoat.actor(uid), origin: Platform::Stoat, caps },
+        };
+        self.tx.send((ev, Some(reply))).await?;
+        self.answer(cid, mid, rx);
         Ok(())
     }
 }
 
 #[::async_trait::async_trait]
 impl Driver for Stoat {
-    async fn notify(&self, p: &Proposal) -> Result<()> {
-        let ch = if p.origin == Platform::Stoat { &p.channel } else { &self.log };
-        let msg = format!("**TPI {}**\nTarget: {}\nReq: {}\nReason: {}\n_React ✅ to approve, ❌ to cancel_", p.action, p.target.stoat.as_deref().unwrap_or(&p.target.raw), p.author, p.reason);
-        let res: Value = self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": msg})).send().await?.error_for_status()?.json().await?;
+    fn platform(&self) -> Platform { Platform::Stoat }
+
+    async fn notify(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>> {
+        let Some(ch) = self.channel(p) else { return Ok(None); };
+        let r = &self.config.reactions;
+        let res: Value = self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": self.render(p)})).send().await?.error_for_status()?.json().await?;
         
-        if let Some(id) = res["_id"].as_str() {
-            let _ = self.http.put(format!("https://stoat.chat/api/channels/{}/messages/{}/reactions/✅", ch, id)).header("x-bot-token", &self.token).send().await;
-            let _ = self.http.put(format!("https://stoat.chat/api/channels/{}/messages/{}/reactions/❌", ch, id)).header("x-bot-token", &self.token).send().await;
-            self.props.write().await.insert(id.into(), p.target.raw.clone());
+        let Some(id) = res["_id"].as_str() else { return Ok(None); };
+        for emoji in [&r.approve, &r.reject, &r.abstain, &r.cancel] {
+            let _ = self.http.put(format!("https://stoat.chat/api/channels/{}/messages/{}/reactions/{}", ch, id, emoji)).header("x-bot-token", &self.token).send().await;
         }
-        Ok(())
+        self.props.write().await.insert(id.into(), (p.tenant.clone(), p.case.to_string()));
+        if let Some(origin) = self.origin(p).filter(|o| p.origin == Platform::Stoat && *o != ch) {
+            let _ = self.say(&origin, &self.text("proposal.ack", p, &[])).await;
+        }
+        Ok(Some(format!("{}:{}", ch, id)))
     }
 
-    async fn execute(&self, p: &Proposal, app: &str) -> Result<()> {
-        let ch = if p.origin == Platform::Stoat { &p.channel } else { &self.log };
-        self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": format!("✅ Executed {} on {} (App: {})", p.action, p.target.raw, app)})).send().await?.error_for_status()?;
+    async fn refresh(&self, p: &Proposal, post: &str) -> Result<()> {
+        let (ch, id) = post.split_once(':').with_context(|| format!("Malformed post reference {}", post))?;
+        self.http.patch(format!("https://stoat.chat/api/channels/{}/messages/{}", ch, id)).header("x-bot-token", &self.token).json(&json!({"content": self.render(p)})).send().await?.error_for_status()?;
         Ok(())
     }
 
+    async fn execute(&self, p: &Proposal, app: &str) -> Result<Report> {
+        let (Some(ch), Some(sid)) = (self.channel(p), self.servers.get(&p.tenant)) else { return Ok(Report::new()); };
+        let targets: ::std::vec::Vec<(&::std::string::String, &str)> = p.subjects().into_iter().filter_map(|i| Some((&i.raw, i.stoat.as_deref()?))).collect();
+        let audit = clip(&format!("Case #{} | {} | Req: {} App: {}", p.case, p.reason, p.author, app), 512);
+        let (mut report, mut lines) = (Report::new(), ::std::vec::Vec::new());
+        for (raw, id) in targets {
+            if !report.is_empty() { ::tokio::time::sleep(::std::time::Duration::from_millis(self.config.bulk_delay_ms)).await; }
+            let req = match p.action.as_str() {
+                "ban" => self.http.put(format!("https://stoat.chat/api/servers/{}/bans/{}", sid, id)).json(&json!({"reason": audit})),
+                "kick" => self.http.delete(format!("https://stoat.chat/api/servers/{}/members/{}", sid, id)),
+                "appeal" => self.http.delete(format!("https://stoat.chat/api/servers/{}/bans/{}", sid, id)),
+                "warn" => match self.dm_channel(id).await {
+                    Ok(dm) => self.http.post(format!("https://stoat.chat/api/channels/{}/messages", dm)).json(&json!({"content": self.text("result.warned", p, &[])})),
+                    Err(e) => { report.push((raw.clone(), Err(format!("{}: {}", id, e)))); lines.push(format!("❌ {}: {}", id, e)); continue; }
+                },
+                "role-add" | "role-remove" if p.origin == Platform::Stoat => {
+                    let Some(role) = p.role.as_deref() else { continue; };
+                    let mut roles = match self.member_roles(sid, id).await {
+                        Ok(r) => r,
+                        Err(e) => { report.push((raw.clone(), Err(format!("{}: {}", id, e)))); lines.push(format!("❌ {}: {}", id, e)); continue; }
+                    };
+                    roles.retain(|r| r != role);
+                    if p.action == "role-add" { roles.push(role.into()); }
+                    self.http.patch(format!("https://stoat.chat/api/servers/{}/members/{}", sid, id)).json(&json!({"roles": roles}))
+                }
+                _ => continue,
+            };
+            let res = req.header("x-bot-token", &self.token).send().await.and_then(|r| r.error_for_status());
+            lines.push(match &res { Ok(_) => format!("✅ {}", id), Err(e) => format!("❌ {}: {}", id, e) });
+            report.push((raw.clone(), res.map(|_| ()).map_err(|e| format!("{}: {}", id, e))));
+        }
+        let done = report.iter().filter(|(_, r)| r.is_ok()).count();
+        if p.batch.is_empty() {
+            if done == report.len() { let _ = self.say(&ch, &self.text("result.executed", p, &[("approver", &Platform::Stoat.mention(app))])).await; }
+        } else {
+            let head = self.text("result.bulk", p, &[("done", &done.to_string()), ("total", &report.len().to_string()), ("approver", &Platform::Stoat.mention(app))]);
+            for chunk in chunk_lines(&head, &lines, 1900) { let _ = self.say(&ch, &chunk).await; }
+        }
+        Ok(report)
+    }
+
     async fn discard(&self, p: &Proposal, reason: &str) -> Result<()> {
-        let ch = if p.origin == Platform::Stoat { &p.channel } else { &self.log };
-        self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": format!("🚫 {} discarded: {}", p.action, reason)})).send().await?.error_for_status()?;
+        for (_, post) in p.posts.iter().filter(|(pl, _)| *pl == Platform::Stoat) {
+            let Some((c, m)) = post.split_once(':') else { continue; };
+            self.props.write().await.remove(m);
+            let _ = self.http.delete(format!("https://stoat.chat/api/channels/{}/messages/{}/reactions", c, m)).header("x-bot-token", &self.token).send().await;
+        }
+        let Some(ch) = self.channel(p) else { return Ok(()); };
+        self.say(&ch, &self.text("result.discarded", p, &[("status", reason)])).await
+    }
+
+    async fn revert(&self, p: &Proposal) -> Result<()> {
+        let targets: ::std::vec::Vec<&str> = p.subjects().into_iter().filter_map(|i| i.stoat.as_deref()).collect();
+        let Some(sid) = self.servers.get(&p.tenant).filter(|_| p.action == "ban" && !targets.is_empty()) else { return Ok(()); };
+        for id in targets {
+            let res = self.http.delete(format!("https://stoat.chat/api/servers/{}/bans/{}", sid, id)).header("x-bot-token", &self.token).send().await.and_then(|r| r.error_for_status());
+            if let Err(e) = res { ::tracing::error!("Stoat unban {}: {}", id, e); }
+        }
+        Ok(())
+    }
+
+    async fn alert(&self, p: &Proposal, text: &str) -> Result<()> {
+        let Some(space) = self.space(p) else { return Ok(()); };
+        self.say(&space.log, text).await
+    }
+
+    async fn relay(&self, p: &Proposal, c: &Comment) -> Result<::std::option::Option<::std::string::String>> {
+        let Some((ch, mid)) = p.posts.iter().find(|(pl, _)| *pl == Platform::Stoat).and_then(|(_, post)| post.split_once(':')) else { return Ok(None); };
+        let text = self.config.text(&p.tenant, &Platform::Stoat, "discussion.relay", &[("author", &Platform::Stoat.mention(&c.author)), ("platform", &format!("{:?}", c.origin)), ("text", &c.text)]);
+        let res: Value = self.http.post(format!("https://stoat.chat/api/channels/{}/messages", ch)).header("x-bot-token", &self.token).json(&json!({"content": text, "replies": [{"id": mid, "mention": false}]})).send().await?.error_for_status()?.json().await?;
+        Ok(res["_id"].as_str().map(::std::string::String::from))
+    }
+
+    async fn message_user(&self, user: &Identity, text: &str) -> Result<()> {
+        let Some(uid) = user.stoat.as_deref() else { return Ok(()); };
+        let dm = self.dm_channel(uid).await?;
+        self.say(&dm, text).await
+    }
+
+    async fn lockdown(&self, p: &Proposal) -> Result<::std::option::Option<::std::string::String>> {
+        let Some(space) = self.space(p).filter(|s| !s.lockdown.is_empty()) else { return Ok(None); };
+        let (mut prior, mut failed) = (::std::vec::Vec::new(), ::std::vec::Vec::new());
+        for cid in &space.lockdown {
+            let res = self.http.get(format!("https://stoat.chat/api/channels/{}", cid)).header("x-bot-token", &self.token).send().await.and_then(|r| r.error_for_status());
+            let chan: Value = match res {
+                Ok(r) => match r.json().await { Ok(v) => v, Err(e) => { failed.push(format!("{}: {}", cid, e)); continue; } },
+                Err(e) => { failed.push(format!("{}: {}", cid, e)); continue; }
+            };
+            let old = Some(&chan["default_permissions"]).filter(|v| !v.is_null()).map(|v| (v["a"].as_i64().unwrap_or(0), v["d"].as_i64().unwrap_or(0)));
+            let (allow, deny) = old.unwrap_or((0, 0));
+            match self.set_default_permissions(cid, allow & !SEND_MESSAGE, deny | SEND_MESSAGE).await {
+                Ok(_) => prior.push((cid.clone(), old)),
+                Err(e) => failed.push(format!("{}: {}", cid, e)),
+            }
+        }
+        let snap = ::serde_json::to_string(&prior)?;
+        if !failed.is_empty() {
+            if let Err(e) = self.restore(p, &snap).await { ::tracing::error!("Stoat lockdown rollback: {}", e); }
+            ::anyhow::bail!("lockdown failed on channel(s) {}", failed.join("; "));
+        }
+        Ok(Some(snap))
+    }
+
+    async fn restore(&self, _: &Proposal, snapshot: &str) -> Result<()> {
+        let prior: ::std::vec::Vec<(::std::string::String, ::std::option::Option<(i64, i64)>)> = ::serde_json::from_str(snapshot)?;
+        let mut failed = ::std::vec::Vec::new();
+        for (cid, old) in prior {
+            let (allow, deny) = old.unwrap_or((0, 0));
+            if let Err(e) = self.set_default_permissions(&cid, allow, deny).await { failed.push(format!("{}: {}", cid, e)); }
+        }
+        ::anyhow::ensure!(failed.is_empty(), "unlock failed on channel(s) {}", failed.join("; "));
         Ok(())
     }
 }
\ No newline at end of file
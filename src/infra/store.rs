@@ -1,46 +1,176 @@
-use crate::domain::{models::Proposal, ports::StateStore};
+use crate::domain::{models::{Infraction, Platform, Proposal}, ports::StateStore};
 use ::anyhow::Result;
 
-pub struct SledStore(::sled::Db);
+pub struct SledStore(::sled::Db, ::std::string::String);
 
 impl SledStore {
     pub fn new(path: &str) -> Result<Self> {
-        Ok(Self(::sled::Config::new().path(path).cache_capacity(64_000_000).open()?))
+        Ok(Self(::sled::Config::new().path(path).cache_capacity(64_000_000).open()?, ::std::string::String::new()))
+    }
+
+    pub fn namespace(&self, ns: &str) -> Self {
+        Self(self.0.clone(), if ns.is_empty() { ns.into() } else { format!("{}/", ns) })
+    }
+
+    fn tree(&self, name: &str) -> Result<::sled::Tree> {
+        Ok(self.0.open_tree(format!("{}{}", self.1, name))?)
+    }
+
+    fn proposals(&self) -> Result<::sled::Tree> {
+        if self.1.is_empty() { Ok((*self.0).clone()) } else { self.tree("proposals") }
     }
 }
 
 #[::async_trait::async_trait]
 impl StateStore for SledStore {
     async fn save(&self, p: Proposal) -> Result<()> {
-        self.0.insert(&p.target.raw, ::serde_json::to_vec(&p)?)?;
-        self.0.flush_async().await?;
+        let proposals = self.proposals()?;
+        proposals.insert(&p.target.raw, ::serde_json::to_vec(&p)?)?;
+        proposals.flush_async().await?;
         Ok(())
     }
     
     async fn get(&self, target: &str) -> Result<::std::option::Option<Proposal>> {
-        if let Some(v) = self.0.get(target)? {
+        let proposals = self.proposals()?;
+        if let Some(v) = proposals.get(target)? {
             match ::serde_json::from_slice(&v) {
                 Ok(p) => return Ok(Some(p)),
-                Err(_) => { let _ = self.0.remove(target); }
+                Err(_) => { let _ = proposals.remove(target); }
             }
         }
         Ok(None)
     }
     
     async fn remove(&self, target: &str) -> Result<()> {
-        self.0.remove(target)?;
-        self.0.flush_async().await?;
+        let proposals = self.proposals()?;
+        proposals.remove(target)?;
+        proposals.flush_async().await?;
         Ok(())
     }
     
     async fn list(&self) -> Result<::std::vec::Vec<Proposal>> {
-        let mut res = ::std::vec::Vec::new();
-        for (k, v) in self.0.iter().flatten() {
+        let (proposals, mut res) = (self.proposals()?, ::std::vec::Vec::new());
+        for (k, v) in proposals.iter().flatten() {
             match ::serde_json::from_slice(&v) {
                 Ok(p) => res.push(p),
-                Err(_) => { let _ = self.0.remove(&k); }
+                Err(_) => { let _ = proposals.remove(&k); }
             }
         }
         Ok(res)
     }
-}
\ No newline at end of file
+
+    async fn next_case(&self) -> Result<u64> {
+        let meta = self.tree("meta")?;
+        let next = meta.update_and_fetch("case", |old| {
+            let n = old.and_then(|b| <[u8; 8]>::try_from(b).ok()).map_or(0, u64::from_be_bytes);
+            Some((n + 1).to_be_bytes().to_vec())
+        })?;
+        meta.flush_async().await?;
+        Ok(next.and_then(|b| <[u8; 8]>::try_from(b.as_ref()).ok()).map_or(0, u64::from_be_bytes))
+    }
+
+    async fn archive(&self, p: &Proposal) -> Result<()> {
+        let cases = self.tree("cases")?;
+        cases.insert(format!("{:020}", p.case), ::serde_json::to_vec(p)?)?;
+        cases.flush_async().await?;
+        Ok(())
+    }
+
+    async fn case(&self, case: u64) -> Result<::std::option::Option<Proposal>> {
+        Ok(self.tree("cases")?.get(format!("{:020}", case))?.and_then(|v| ::serde_json::from_slice(&v).ok()))
+    }
+
+    async fn audit(&self, key: &str, entry: &str) -> Result<()> {
+        let trail = self.tree("audit")?;
+        trail.insert(format!("{}:{:020}", key, self.0.generate_id()?), entry.as_bytes())?;
+        trail.flush_async().await?;
+        Ok(())
+    }
+
+    async fn trail(&self, key: &str) -> Result<::std::vec::Vec<::std::string::String>> {
+        let trail = self.tree("audit")?;
+        Ok(trail.scan_prefix(format!("{}:", key)).values().flatten().map(|v| ::std::string::String::from_utf8_lossy(&v).into_owned()).collect())
+    }
+
+    async fn record(&self, user: &str, infraction: &Infraction) -> Result<()> {
+        let infractions = self.tree("infractions")?;
+        infractions.insert(format!("{}:{:020}", user, self.0.generate_id()?), ::serde_json::to_vec(infraction)?)?;
+        infractions.flush_async().await?;
+        Ok(())
+    }
+
+    async fn infractions(&self, user: &str) -> Result<::std::vec::Vec<Infraction>> {
+        let infractions = self.tree("infractions")?;
+        Ok(infractions.scan_prefix(format!("{}:", user)).values().flatten().filter_map(|v| ::serde_json::from_slice(&v).ok()).collect())
+    }
+
+    async fn set_link(&self, discord: &str, stoat: &str) -> Result<()> {
+        let links = self.tree("links")?;
+        for (key, other) in [(format!("discord:{}", discord), "stoat"), (format!("stoat:{}", stoat), "discord")] {
+            if let Some(old) = links.remove(&key)? { links.remove(format!("{}:{}", other, ::std::string::String::from_utf8_lossy(&old)))?; }
+        }
+        links.insert(format!("discord:{}", discord), stoat.as_bytes())?;
+        links.insert(format!("stoat:{}", stoat), discord.as_bytes())?;
+        links.flush_async().await?;
+        Ok(())
+    }
+
+    async fn linked(&self, platform: &Platform, id: &str) -> Result<::std::option::Option<::std::string::String>> {
+        let key = match platform { Platform::Discord => format!("discord:{}", id), Platform::Stoat => format!("stoat:{}", id) };
+        Ok(self.tree("links")?.get(key)?.map(|v| ::std::string::String::from_utf8_lossy(&v).into_owned()))
+    }
+
+    async fn snapshot(&self, key: &str) -> Result<::std::option::Option<::std::string::String>> {
+        Ok(self.tree("snapshots")?.get(key)?.map(|v| ::std::string::String::from_utf8_lossy(&v).into_owned()))
+    }
+
+    async fn set_snapshot(&self, key: &str, snapshot: ::std::option::Option<&str>) -> Result<()> {
+        let snapshots = self.tree("snapshots")?;
+        match snapshot {
+            Some(s) => { snapshots.insert(key, s.as_bytes())?; }
+            None => { snapshots.remove(key)?; }
+        }
+        snapshots.flush_async().await?;
+        Ok(())
+    }
+
+    async fn index(&self, post: &str, case: ::std::option::Option<(u64, &str)>) -> Result<()> {
+        let posts = self.tree("posts")?;
+        match case {
+            Some((n, target)) => { posts.insert(post, format!("{}:{}", n, target).as_bytes())?; }
+            None => { posts.remove(post)?; }
+        }
+        Ok(())
+    }
+
+    async fn indexed(&self, post: &str) -> Result<::std::option::Option<(u64, ::std::string::String)>> {
+        let Some(v) = self.tree("posts")?.get(post)? else { return Ok(None); };
+        let v = ::std::string::String::from_utf8_lossy(&v);
+        Ok(v.split_once(':').and_then(|(n, t)| Some((n.parse().ok()?, t.to_string()))))
+    }
+
+    async fn schedule(&self, subject: &str, expiry: ::std::option::Option<(u64, &Proposal)>) -> Result<()> {
+        let expiries = self.tree("expiries")?;
+        match expiry {
+            Some(e) => { expiries.insert(subject, ::serde_json::to_vec(&e)?)?; }
+            None => { expiries.remove(subject)?; }
+        }
+        expiries.flush_async().await?;
+        Ok(())
+    }
+
+    async fn expired(&self, now: u64) -> Result<::std::vec::Vec<Proposal>> {
+        let expiries = self.tree("expiries")?;
+        let mut due = ::std::vec::Vec::new();
+        for (k, v) in expiries.iter().flatten() {
+            match ::serde_json::from_slice::<(u64, Proposal)>(&v) {
+                Ok((until, _)) if until > now => continue,
+                Ok((_, p)) => due.push(p),
+                Err(e) => ::tracing::error!("Dropping unreadable expiry: {}", e),
+            }
+            expiries.remove(k)?;
+        }
+        if !due.is_empty() { expiries.flush_async().await?; }
+        Ok(due)
+    }
+}
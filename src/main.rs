@@ -3,7 +3,7 @@ mod domain;
 mod infra;
 
 use crate::config::AppConfig;
-use crate::domain::{engine::Core, models::Event, ports::Driver};
+use crate::domain::{engine::Core, models::{Envelope, Event}, ports::{Driver, StateStore}};
 use crate::infra::{discord::Discord, stoat::Stoat, store::SledStore};
 use ::std::{env, sync::Arc};
 use ::tokio::sync::mpsc;
@@ -14,41 +14,28 @@ async fn main() -> ::anyhow::Result<()> {
     ::tracing_subscriber::fmt::init();
 
     let cfg = Arc::new(AppConfig::load("config.toml")?);
-    let (tx, mut rx) = mpsc::channel::<Event>(100);
-    let store = Arc::new(SledStore::new("./urchin_db")?);
+    let (tx, mut rx) = mpsc::channel::<Envelope>(100);
+    let db = SledStore::new("./urchin_db")?;
+    let stores = cfg.tenants.iter().map(|t| (t.name.clone(), Arc::new(db.namespace(&t.namespace())) as Arc<dyn StateStore>)).collect();
 
-    let discord = Arc::new(Discord::new(
-        &env::var("DISCORD_TOKEN")?,
-        env::var("DISCORD_GUILD_ID")?.parse()?,
-        env::var("DISCORD_STAFF_ROLE_ID")?.parse()?,
-        env::var("DISCORD_LOG_CHANNEL_ID")?.parse()?,
-        tx.clone(),
-        Arc::clone(&cfg)
-    ).await?) as Arc<dyn Driver>;
+    let discord = Arc::new(Discord::new(&env::var("DISCORD_TOKEN")?, tx.clone(), Arc::clone(&cfg)).await?) as Arc<dyn Driver>;
+    let stoat = Arc::new(Stoat::new(&env::var("STOAT_TOKEN")?, tx.clone(), Arc::clone(&cfg)).await?) as Arc<dyn Driver>;
 
-    let stoat = Arc::new(Stoat::new(
-        &env::var("STOAT_TOKEN")?,
-        &env::var("STOAT_LOG_CHANNEL_ID")?,
-        &env::var("STOAT_STAFF_ROLE_ID")?,
-        tx.clone(),
-        Arc::clone(&cfg)
-    ).await?) as Arc<dyn Driver>;
-
-    let core = Core::new(store, vec![discord, stoat], Arc::clone(&cfg));
+    let core = Core::new(stores, vec![discord, stoat], Arc::clone(&cfg));
 
     let tx_sweep = tx.clone();
     ::tokio::spawn(async move {
         let mut interval = ::tokio::time::interval(::std::time::Duration::from_secs(60));
         loop {
             interval.tick().await;
-            let _ = tx_sweep.send(Event::Sweep).await;
+            let _ = tx_sweep.send((Event::Sweep, None)).await;
         }
     });
 
-    while let Some(ev) = rx.recv().await {
-        if let Err(e) = core.run(ev).await {
-            ::tracing::error!("Kernel Exception: {:#}", e);
-        }
+    while let Some((ev, reply)) = rx.recv().await {
+        let res = core.run(ev).await;
+        if let Err(e) = &res { ::tracing::error!("Kernel Exception: {:#}", e); }
+        if let Some(r) = reply { let _ = r.send(res.map_err(|e| format!("{:#}", e))); }
     }
     
     Ok(())